@@ -1,6 +1,6 @@
 use std::{
     fmt,
-    io::{Error as IoError, Read, Seek, SeekFrom},
+    io::{Error as IoError, Read},
     mem::size_of,
 };
 
@@ -101,26 +101,27 @@ pub(crate) struct Header {
     large_banner_path_remainder: Vec<u8>,
     last_battle_gold: u16,
     gold_in_coffers: u16,
-    magic_items: [u8; 40],
+    magic_items: [u8; Army::MAX_MAGIC_ITEMS],
     unknown3: [u8; 2], // purpose of bytes at index 190 and 191 is unknown
 }
 
+/// Decodes an army from an `.ARM`, `.AUD` or `.ARE` file, or from a save game.
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
 
     pub fn decode(&mut self) -> Result<Army, DecodeError> {
-        let (start_pos, save_game_header) = self.maybe_read_save_game_header()?;
+        let (format_buf, save_game_header) = self.maybe_read_save_game_header()?;
 
-        let header = self.read_header(start_pos)?;
+        let header = self.read_header(format_buf)?;
 
         let race =
             ArmyRace::from_bits(header.race).ok_or(DecodeError::InvalidArmyRace(header.race))?;
@@ -177,6 +178,31 @@ impl<R: Read + Seek> Decoder<R> {
         })
     }
 
+    /// Decodes only the save game header, if there is one, and the army header,
+    /// i.e. everything that comes before the regiments.
+    ///
+    /// Exactly 192 bytes are read for an army file and 696 bytes for a save
+    /// game, which has a 504 byte save game header before the army header. The
+    /// regiments and the save game footer are not read, so the reader is left
+    /// positioned at the first regiment.
+    pub fn decode_header_only(&mut self) -> Result<ArmySummary, DecodeError> {
+        let (format_buf, save_game_header) = self.maybe_read_save_game_header()?;
+
+        let header = self.read_header(format_buf)?;
+
+        let race =
+            ArmyRace::from_bits(header.race).ok_or(DecodeError::InvalidArmyRace(header.race))?;
+
+        Ok(ArmySummary {
+            save_game_header,
+            race,
+            default_name_index: header.default_name_index,
+            name: header.name,
+            regiment_count: header.regiment_count,
+            gold_in_coffers: header.gold_in_coffers,
+        })
+    }
+
     fn read_script_state(&mut self, buf: &[u8]) -> Result<ScriptState, DecodeError> {
         let unknown2 = buf[28..100].to_vec();
         let unknown7 = buf[136..].to_vec();
@@ -231,19 +257,22 @@ impl<R: Read + Seek> Decoder<R> {
         })
     }
 
+    /// Reads the save game header, if there is one.
+    ///
+    /// Also returns the first 4 bytes of the army header, i.e. its format,
+    /// which have to be read to tell whether there is a save game header.
     fn maybe_read_save_game_header(
         &mut self,
-    ) -> Result<(u64, Option<SaveGameHeader>), DecodeError> {
-        let mut buf = [0; size_of::<u32>()];
-        self.reader.read_exact(&mut buf)?;
+    ) -> Result<([u8; size_of::<u32>()], Option<SaveGameHeader>), DecodeError> {
+        let mut format_buf = [0; size_of::<u32>()];
+        self.reader.read_exact(&mut format_buf)?;
 
-        let format = u32::from_le_bytes(buf[0..size_of::<u32>()].try_into().unwrap());
+        let format = u32::from_le_bytes(format_buf);
 
         if format != FORMAT {
-            self.reader.seek(SeekFrom::Start(0))?;
-
             let mut buf = vec![0; SAVE_GAME_HEADER_SIZE_BYTES];
-            self.reader.read_exact(&mut buf)?;
+            buf[..size_of::<u32>()].copy_from_slice(&format_buf);
+            self.reader.read_exact(&mut buf[size_of::<u32>()..])?;
 
             let display_name_buf = &buf[0..SAVE_GAME_DISPLAY_NAME_SIZE_BYTES];
             let (display_name_buf, display_name_residual_bytes) = display_name_buf
@@ -267,8 +296,10 @@ impl<R: Read + Seek> Decoder<R> {
 
             let script_state = self.read_script_state(&script_state_buf)?;
 
+            self.reader.read_exact(&mut format_buf)?;
+
             return Ok((
-                SAVE_GAME_HEADER_SIZE_BYTES as u64,
+                format_buf,
                 Some(SaveGameHeader {
                     display_name: self.read_string(display_name_buf)?,
                     display_name_residual_bytes: if display_name_residual_bytes
@@ -348,7 +379,7 @@ impl<R: Read + Seek> Decoder<R> {
             ));
         }
 
-        Ok((0, None))
+        Ok((format_buf, None))
     }
 
     fn maybe_read_save_game_footer(&mut self) -> Result<Option<SaveGameFooter>, DecodeError> {
@@ -488,11 +519,15 @@ impl<R: Read + Seek> Decoder<R> {
         })
     }
 
-    fn read_header(&mut self, start_pos: u64) -> Result<Header, DecodeError> {
-        self.reader.seek(SeekFrom::Start(start_pos))?;
+    fn read_header(&mut self, format_buf: [u8; size_of::<u32>()]) -> Result<Header, DecodeError> {
+        let format = u32::from_le_bytes(format_buf);
+        if format != FORMAT {
+            return Err(DecodeError::InvalidFormat(format));
+        }
 
         let mut buf = [0; HEADER_SIZE_BYTES];
-        self.reader.read_exact(&mut buf)?;
+        buf[..size_of::<u32>()].copy_from_slice(&format_buf);
+        self.reader.read_exact(&mut buf[size_of::<u32>()..])?;
 
         let army_name_buf = &buf[18..50];
         let (army_name_buf, army_name_remainder) = army_name_buf
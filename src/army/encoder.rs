@@ -11,11 +11,26 @@ use crate::army::decoder::{
 
 use super::*;
 
+/// The size of each of the army name and banner path buffers in the army
+/// header.
+const HEADER_STRING_SIZE_BYTES: usize = 32;
+/// The size of the display name buffer in a unit profile.
+const UNIT_PROFILE_DISPLAY_NAME_SIZE_BYTES: usize = 32;
+
 #[derive(Debug)]
 pub enum EncodeError {
     IoError(std::io::Error),
     InvalidString,
-    StringTooLong,
+    /// A string does not fit in the fixed-size buffer the game reads it from.
+    /// `max` is the size of the buffer in bytes, including the nul terminator.
+    /// Residual bytes kept from decoding do not count towards it.
+    StringTooLong {
+        field: &'static str,
+        max: usize,
+    },
+    /// The army has more magic items than fit in its inventory. See
+    /// [`Army::MAX_MAGIC_ITEMS`].
+    TooManyMagicItems(usize),
 }
 
 impl std::error::Error for EncodeError {}
@@ -31,7 +46,15 @@ impl std::fmt::Display for EncodeError {
         match self {
             EncodeError::IoError(e) => write!(f, "IO error: {}", e),
             EncodeError::InvalidString => write!(f, "invalid string"),
-            EncodeError::StringTooLong => write!(f, "string too long"),
+            EncodeError::StringTooLong { field, max } => {
+                write!(f, "string too long: {} must fit in {} bytes", field, max)
+            }
+            EncodeError::TooManyMagicItems(count) => write!(
+                f,
+                "too many magic items: {} (max {})",
+                count,
+                Army::MAX_MAGIC_ITEMS
+            ),
         }
     }
 }
@@ -39,15 +62,45 @@ impl std::fmt::Display for EncodeError {
 #[derive(Debug)]
 pub struct Encoder<W: Write> {
     writer: BufWriter<W>,
+    normalize: bool,
 }
 
 impl<W: Write> Encoder<W> {
     pub fn new(writer: W) -> Self {
         Encoder {
             writer: BufWriter::new(writer),
+            normalize: false,
         }
     }
 
+    /// Makes the encoder write clean, canonical output instead of reproducing
+    /// the decoded army byte for byte.
+    ///
+    /// In this mode, the bytes after the army header's `race` that look like
+    /// padding are zeroed, and the residual bytes left behind in string
+    /// buffers by the game are cleared. Everything else, including fields whose
+    /// meaning is unknown, is written as is.
+    ///
+    /// By default, the encoder faithfully round-trips the decoded army.
+    pub fn normalized(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+
+    /// Encodes the army.
+    ///
+    /// Strings are written to fixed-size buffers, so encoding fails with
+    /// [`EncodeError::StringTooLong`] if any of the following, encoded as
+    /// Windows-1252 and including the nul terminator, do not fit:
+    ///
+    /// - the army name and banner paths: 32 bytes each;
+    /// - a unit profile's display name: 32 bytes;
+    /// - a save game's display name and suggested display name: 90 bytes each;
+    /// - a save game's background image path and cutscene animation paths: 256
+    ///   bytes each.
+    ///
+    /// Residual bytes kept from decoding never count towards the limit. They
+    /// are dropped as needed for every string.
     pub fn encode(&mut self, army: &Army) -> Result<(), EncodeError> {
         self.maybe_write_save_game_header(army)?;
         self.write_header(army)?;
@@ -86,37 +139,18 @@ impl<W: Write> Encoder<W> {
             return Ok(());
         };
 
-        let display_name_bytes_written = self.write_string(&header.display_name)?;
-        if let Some(display_name_residual_bytes) = header.display_name_residual_bytes.as_ref() {
-            let padding_size_bytes = SAVE_GAME_DISPLAY_NAME_SIZE_BYTES
-                - (display_name_bytes_written + display_name_residual_bytes.len());
-            let padding = vec![0; padding_size_bytes];
-            self.writer.write_all(display_name_residual_bytes)?;
-            self.writer.write_all(&padding)?;
-        } else {
-            let padding_size_bytes = SAVE_GAME_DISPLAY_NAME_SIZE_BYTES - display_name_bytes_written;
-            let padding = vec![0; padding_size_bytes];
-            self.writer.write_all(&padding)?;
-        }
-
-        let suggested_display_name_bytes_written =
-            self.write_string(&header.suggested_display_name)?;
-        if let Some(suggested_display_name_residual_bytes) =
-            header.suggested_display_name_residual_bytes.as_ref()
-        {
-            let padding_size_bytes = SAVE_GAME_DISPLAY_NAME_SIZE_BYTES
-                - (suggested_display_name_bytes_written
-                    + suggested_display_name_residual_bytes.len());
-            let padding = vec![0; padding_size_bytes];
-            self.writer
-                .write_all(suggested_display_name_residual_bytes)?;
-            self.writer.write_all(&padding)?;
-        } else {
-            let padding_size_bytes =
-                SAVE_GAME_DISPLAY_NAME_SIZE_BYTES - suggested_display_name_bytes_written;
-            let padding = vec![0; padding_size_bytes];
-            self.writer.write_all(&padding)?;
-        }
+        self.write_string_with_residual_bytes(
+            &header.display_name,
+            header.display_name_residual_bytes.as_deref(),
+            SAVE_GAME_DISPLAY_NAME_SIZE_BYTES,
+            "display_name",
+        )?;
+        self.write_string_with_residual_bytes(
+            &header.suggested_display_name,
+            header.suggested_display_name_residual_bytes.as_deref(),
+            SAVE_GAME_DISPLAY_NAME_SIZE_BYTES,
+            "suggested_display_name",
+        )?;
 
         self.writer
             .write_all(&(if header.unknown_bool1 { 1u32 } else { 0u32 }).to_le_bytes())?;
@@ -238,23 +272,12 @@ impl<W: Write> Encoder<W> {
         self.writer.write_all(&footer.unknown1)?;
 
         let background_image_path = footer.background_image_path.as_ref().map_or("", |s| s);
-        let background_image_path_bytes_written = self.write_string(background_image_path)?;
-        if let Some(background_image_path_residual_bytes) =
-            footer.background_image_path_residual_bytes.as_ref()
-        {
-            let padding_size_bytes = SAVE_GAME_ASSET_PATH_SIZE_BYTES
-                - (background_image_path_bytes_written
-                    + background_image_path_residual_bytes.len());
-            let padding = vec![0; padding_size_bytes];
-            self.writer
-                .write_all(background_image_path_residual_bytes)?;
-            self.writer.write_all(&padding)?;
-        } else {
-            let padding_size_bytes =
-                SAVE_GAME_ASSET_PATH_SIZE_BYTES - background_image_path_bytes_written;
-            let padding = vec![0; padding_size_bytes];
-            self.writer.write_all(&padding)?;
-        }
+        self.write_string_with_residual_bytes(
+            background_image_path,
+            footer.background_image_path_residual_bytes.as_deref(),
+            SAVE_GAME_ASSET_PATH_SIZE_BYTES,
+            "background_image_path",
+        )?;
 
         for v in footer.unknown2.iter() {
             self.writer.write_all(&v.to_le_bytes())?;
@@ -275,7 +298,11 @@ impl<W: Write> Encoder<W> {
         self.writer.write_all(&a.unknown1.to_le_bytes())?;
         self.writer.write_all(&a.position.x.to_le_bytes())?;
         self.writer.write_all(&a.position.y.to_le_bytes())?;
-        self.write_string_with_limit(&a.path, SAVE_GAME_ASSET_PATH_SIZE_BYTES)?;
+        self.write_string_with_limit(
+            &a.path,
+            SAVE_GAME_ASSET_PATH_SIZE_BYTES,
+            "cutscene_animation.path",
+        )?;
         self.writer.write_all(&a.unknown2.to_le_bytes())?;
         self.writer.write_all(&a.unknown3.to_le_bytes())?;
         self.writer.write_all(&a.sprite_count.to_le_bytes())?;
@@ -286,29 +313,49 @@ impl<W: Write> Encoder<W> {
     }
 
     fn write_header(&mut self, army: &Army) -> Result<(), EncodeError> {
+        if army.magic_items.len() > Army::MAX_MAGIC_ITEMS {
+            return Err(EncodeError::TooManyMagicItems(army.magic_items.len()));
+        }
+
         self.writer.write_all(&FORMAT.to_le_bytes())?;
         self.writer
             .write_all(&(army.regiments.len() as u32).to_le_bytes())?;
         self.writer
             .write_all(&(REGIMENT_SIZE_BYTES as u32).to_le_bytes())?;
         self.writer.write_all(&army.race.bits().to_le_bytes())?;
-        self.writer.write_all(&army.unknown1)?;
+        if self.normalize {
+            self.writer.write_all(&[0; 3])?;
+        } else {
+            self.writer.write_all(&army.unknown1)?;
+        }
         self.writer
             .write_all(&army.default_name_index.to_le_bytes())?;
-        self.write_string(&army.name)?;
-        self.writer.write_all(&army.name_remainder)?;
-        self.write_string(&army.small_banner_path)?;
-        self.writer.write_all(&army.small_banner_path_remainder)?;
-        self.write_string(&army.small_disabled_banner_path)?;
-        self.writer
-            .write_all(&army.small_disabled_banner_path_remainder)?;
-        self.write_string(&army.large_banner_path)?;
-        self.writer.write_all(&army.large_banner_path_remainder)?;
+        self.write_string_with_remainder(&army.name, &army.name_remainder, "name")?;
+        self.write_string_with_remainder(
+            &army.small_banner_path,
+            &army.small_banner_path_remainder,
+            "small_banner_path",
+        )?;
+        self.write_string_with_remainder(
+            &army.small_disabled_banner_path,
+            &army.small_disabled_banner_path_remainder,
+            "small_disabled_banner_path",
+        )?;
+        self.write_string_with_remainder(
+            &army.large_banner_path,
+            &army.large_banner_path_remainder,
+            "large_banner_path",
+        )?;
         self.writer
             .write_all(&army.last_battle_gold.to_le_bytes())?;
         self.writer.write_all(&army.gold_in_coffers.to_le_bytes())?;
         self.writer.write_all(&army.magic_items)?;
-        self.writer.write_all(&army.unknown3)?;
+        self.writer
+            .write_all(&[0; Army::MAX_MAGIC_ITEMS][army.magic_items.len()..])?;
+        let mut unknown3 = [0; 2];
+        let unknown3_len = army.unknown3.len().min(unknown3.len());
+        unknown3[..unknown3_len].copy_from_slice(&army.unknown3[..unknown3_len]);
+        self.writer.write_all(&unknown3)?;
 
         self.writer.flush()?;
 
@@ -364,7 +411,11 @@ impl<W: Write> Encoder<W> {
 
     fn write_unit_profile(&mut self, u: &UnitProfile) -> Result<(), EncodeError> {
         self.writer.write_all(&u.sprite_sheet_index.to_le_bytes())?;
-        self.write_string_with_limit(&u.display_name, 32)?;
+        self.write_string_with_limit(
+            &u.display_name,
+            UNIT_PROFILE_DISPLAY_NAME_SIZE_BYTES,
+            "unit_profile.display_name",
+        )?;
         self.writer.write_all(&u.display_name_id.to_le_bytes())?;
         self.writer.write_all(&[Into::<u8>::into(u.alignment)])?;
         self.writer.write_all(&[u.max_unit_count])?;
@@ -400,28 +451,66 @@ impl<W: Write> Encoder<W> {
         Ok(())
     }
 
-    fn write_string(&mut self, s: &str) -> Result<usize, EncodeError> {
-        let (windows_1252_bytes, _, _) = WINDOWS_1252.encode(s);
+    /// Writes a string to one of the army header's fixed-size buffers,
+    /// followed by the bytes that were after the string's nul terminator when
+    /// it was decoded.
+    fn write_string_with_remainder(
+        &mut self,
+        s: &str,
+        remainder: &[u8],
+        field: &'static str,
+    ) -> Result<(), EncodeError> {
+        self.write_string_with_residual_bytes(s, Some(remainder), HEADER_STRING_SIZE_BYTES, field)
+    }
 
-        let c_string = CString::new(windows_1252_bytes).map_err(|_| EncodeError::InvalidString)?;
-        let bytes = c_string.as_bytes_with_nul();
+    /// Writes a string to a fixed-size buffer of `limit` bytes, followed by its
+    /// residual bytes, if any.
+    ///
+    /// Residual bytes that no longer fit after the string are dropped, e.g.
+    /// when a decoded string is edited to be longer, so only the string itself
+    /// has to fit in the buffer.
+    fn write_string_with_residual_bytes(
+        &mut self,
+        s: &str,
+        residual_bytes: Option<&[u8]>,
+        limit: usize,
+        field: &'static str,
+    ) -> Result<(), EncodeError> {
+        let bytes = make_c_string(s)?;
 
-        self.writer.write_all(bytes)?;
+        if bytes.len() > limit {
+            return Err(EncodeError::StringTooLong { field, max: limit });
+        }
 
-        Ok(bytes.len())
-    }
+        let residual_bytes = match residual_bytes {
+            Some(residual_bytes) if !self.normalize => residual_bytes,
+            _ => &[],
+        };
+        let residual_bytes = &residual_bytes[..residual_bytes.len().min(limit - bytes.len())];
 
-    fn write_string_with_limit(&mut self, s: &str, limit: usize) -> Result<(), EncodeError> {
-        let (windows_1252_bytes, _, _) = WINDOWS_1252.encode(s);
+        self.writer.write_all(&bytes)?;
+        self.writer.write_all(residual_bytes)?;
 
-        let c_string = CString::new(windows_1252_bytes).map_err(|_| EncodeError::InvalidString)?;
-        let bytes = c_string.as_bytes_with_nul();
+        let padding_size_bytes = limit - (bytes.len() + residual_bytes.len());
+        let padding = vec![0; padding_size_bytes];
+        self.writer.write_all(&padding)?;
+
+        Ok(())
+    }
+
+    fn write_string_with_limit(
+        &mut self,
+        s: &str,
+        limit: usize,
+        field: &'static str,
+    ) -> Result<(), EncodeError> {
+        let bytes = make_c_string(s)?;
 
         if bytes.len() > limit {
-            return Err(EncodeError::StringTooLong);
+            return Err(EncodeError::StringTooLong { field, max: limit });
         }
 
-        self.writer.write_all(bytes)?;
+        self.writer.write_all(&bytes)?;
 
         let padding_size_bytes = limit - bytes.len();
         let padding = vec![0; padding_size_bytes];
@@ -430,3 +519,13 @@ impl<W: Write> Encoder<W> {
         Ok(())
     }
 }
+
+/// Encodes the string as Windows-1252 and returns its bytes, including the nul
+/// terminator.
+fn make_c_string(s: &str) -> Result<Vec<u8>, EncodeError> {
+    let (windows_1252_bytes, _, _) = WINDOWS_1252.encode(s);
+
+    let c_string = CString::new(windows_1252_bytes).map_err(|_| EncodeError::InvalidString)?;
+
+    Ok(c_string.into_bytes_with_nul())
+}
@@ -8,6 +8,9 @@ use derive_more::derive::{Display, Error, From};
 use glam::UVec2;
 use num_enum::{IntoPrimitive, TryFromPrimitive, TryFromPrimitiveError};
 use serde::{Deserialize, Serialize};
+use std::{fmt, path::Path, str::FromStr, sync::Arc};
+
+use crate::cache::DecodeCache;
 
 pub use decoder::{DecodeError, Decoder};
 pub use encoder::{EncodeError, Encoder};
@@ -36,7 +39,9 @@ pub struct ScriptState {
     /// Initialized to 20 on init.
     pub stack_pointer: u32,
     unknown2: Vec<u8>,
-    unknown2_hex: Vec<String>,  // TODO: Remove, debug only.
+    #[serde(skip)]
+    unknown2_hex: Vec<String>, // TODO: Remove, debug only.
+    #[serde(skip)]
     unknown2_as_u32s: Vec<u32>, // TODO: Remove, debug only.
     /// The offset index to add to the base execution address to get the address
     /// to start executing from. To account for alignment, multiply this value
@@ -59,7 +64,9 @@ pub struct ScriptState {
     /// Initialized to 0 on init.
     pub unknown6: u32,
     unknown7: Vec<u8>,
-    unknown7_hex: Vec<String>,  // TODO: Remove, debug only.
+    #[serde(skip)]
+    unknown7_hex: Vec<String>, // TODO: Remove, debug only.
+    #[serde(skip)]
     unknown7_as_u32s: Vec<u32>, // TODO: Remove, debug only.
 }
 
@@ -93,7 +100,9 @@ pub struct SaveGameHeader {
     suggested_display_name_residual_bytes: Option<Vec<u8>>,
     pub unknown_bool1: bool,
     pub unknown_bool2: bool,
-    script_state_hex: Vec<String>,  // TODO: Remove, debug only.
+    #[serde(skip)]
+    script_state_hex: Vec<String>, // TODO: Remove, debug only.
+    #[serde(skip)]
     script_state_as_u32s: Vec<u32>, // TODO: Remove, debug only.
     /// The script state of the save game. Used by the WHMTG scripting engine to
     /// run the next part of the campaign.
@@ -141,6 +150,52 @@ pub struct SaveGameHeader {
     pub previous_answer: u32,
 }
 
+impl SaveGameHeader {
+    /// Returns a readable list of the campaign missions that have been played
+    /// and the major enemies that have been defeated, in campaign order.
+    pub fn completed_missions(&self) -> Vec<&'static str> {
+        [
+            (self.bogenhafen_mission, "Bogenhafen completed"),
+            (self.goblin_camp_mission, "Goblin Camp completed"),
+            (self.ragnar_mission, "Ragnar's trolls completed"),
+            (self.vingtienne_mission, "Vingtienne completed"),
+            (self.treeman_mission, "Loren Lake completed"),
+            (self.helmgart_mission, "Helmgart completed"),
+            (self.axebite_mission, "Axebite Pass completed"),
+            (self.carstein_defeated, "Manfred von Carstein defeated"),
+            (self.hand_of_nagash_defeated, "Hand of Nagash defeated"),
+            (self.black_grail_defeated, "Black Grail defeated"),
+        ]
+        .into_iter()
+        .filter_map(|(completed, name)| completed.then_some(name))
+        .collect()
+    }
+
+    /// Returns a readable summary of the choices the player has made in the
+    /// campaign, together with whether each has been made.
+    ///
+    /// Unlike [`SaveGameHeader::completed_missions`], every decision is
+    /// returned so that a save game browser can show both outcomes.
+    pub fn decisions(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            (
+                "Attacked the Goblin Camp or helped Ragnar",
+                self.goblin_camp_or_ragnar,
+            ),
+            (
+                "Agreed to help Ragnar",
+                self.ragnar_mission_pre_battle || self.ragnar_mission,
+            ),
+            (
+                "Attacked Vingtienne or helped the Treeman",
+                self.vingtienne_or_treeman,
+            ),
+            ("Met King Orion", self.loren_king_met),
+            ("Previous battle won", self.previous_battle_won_1),
+        ]
+    }
+}
+
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct CutsceneAnimation {
@@ -164,7 +219,9 @@ pub struct CutsceneAnimation {
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct SaveGameFooter {
     unknown1: Vec<u8>,
+    #[serde(skip)]
     unknown1_as_u16s: Vec<u16>, // TODO: Remove, debug only.
+    #[serde(skip)]
     unknown1_as_u32s: Vec<u32>, // TODO: Remove, debug only.
     /// The path to the background image file, e.g. "[PICTURES]\m_empn.bmp".
     pub background_image_path: Option<String>,
@@ -187,66 +244,255 @@ pub struct SaveGameFooter {
     /// battles.
     pub cutscene_animations: Vec<CutsceneAnimation>,
     unknown3: Vec<u8>,
+    #[serde(skip)]
     unknown3_as_u16s: Vec<u16>, // TODO: Remove, debug only.
+    #[serde(skip)]
     unknown3_as_u32s: Vec<u32>, // TODO: Remove, debug only.
-    hex: Vec<String>,           // TODO: Remove, debug only.
+    #[serde(skip)]
+    hex: Vec<String>, // TODO: Remove, debug only.
+}
+
+impl SaveGameFooter {
+    /// Returns the paths of the images needed to render the cutscene screen
+    /// shown in between battles, i.e. the background image and the sprite
+    /// sheets of the enabled cutscene animations.
+    pub fn referenced_image_paths(&self) -> Vec<&str> {
+        self.background_image_path
+            .iter()
+            .map(String::as_str)
+            .chain(
+                self.cutscene_animations
+                    .iter()
+                    .filter(|animation| animation.enabled)
+                    .map(|animation| animation.path.as_str()),
+            )
+            .collect()
+    }
+}
+
+/// A view of an army that is known to be a save game. See
+/// [`Army::as_save_game`].
+#[derive(Clone, Copy, Debug)]
+pub struct SaveGameView<'a> {
+    army: &'a Army,
+    header: &'a SaveGameHeader,
+    footer: &'a SaveGameFooter,
+}
+
+impl<'a> SaveGameView<'a> {
+    /// Returns the army.
+    pub fn army(&self) -> &'a Army {
+        self.army
+    }
+
+    /// Returns the save game header. This includes the campaign's progress,
+    /// e.g. which missions have been played.
+    pub fn header(&self) -> &'a SaveGameHeader {
+        self.header
+    }
+
+    /// Returns the save game footer.
+    pub fn footer(&self) -> &'a SaveGameFooter {
+        self.footer
+    }
+
+    /// Returns the name displayed when loading the save game.
+    pub fn display_name(&self) -> &'a str {
+        &self.header.display_name
+    }
+
+    /// Returns the name suggested when saving the game.
+    pub fn suggested_display_name(&self) -> &'a str {
+        &self.header.suggested_display_name
+    }
+
+    /// Returns the script state used to run the next part of the campaign.
+    pub fn script_state(&self) -> &'a ScriptState {
+        &self.header.script_state
+    }
+
+    /// Returns the army's regiments.
+    pub fn regiments(&self) -> &'a [Regiment] {
+        &self.army.regiments
+    }
+
+    /// Returns the animations used on the cutscene screens shown in between
+    /// battles.
+    pub fn cutscene_animations(&self) -> &'a [CutsceneAnimation] {
+        &self.footer.cutscene_animations
+    }
+}
+
+/// The kinds of file that contain an army.
+///
+/// All kinds share the same format and cannot be told apart by their contents,
+/// so the kind is only known from the file's extension. See
+/// [`FileKind::from_path`]. Save games additionally have a header before the
+/// army and a footer after it. See [`Army::save_game_header`] and
+/// [`Army::save_game_footer`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+pub enum FileKind {
+    /// An army file, e.g. `PLYR_ALG.ARM`.
+    Arm,
+    /// An army file with the `.AUD` extension.
+    Aud,
+    /// An army file with the `.ARE` extension.
+    Are,
+    /// A save game, e.g. `SAVEGAME.000`.
+    SaveGame,
+}
+
+impl FileKind {
+    /// Returns the kind of file based on the extension of `path`, or `None` if
+    /// the extension is not one used for armies.
+    ///
+    /// Save games use a 3-digit extension, e.g. `000`.
+    pub fn from_path(path: &Path) -> Option<FileKind> {
+        let ext = path.extension()?.to_string_lossy().to_uppercase();
+        match ext.as_str() {
+            "ARM" => Some(FileKind::Arm),
+            "AUD" => Some(FileKind::Aud),
+            "ARE" => Some(FileKind::Are),
+            _ if ext.len() == 3 && ext.chars().all(|c| c.is_ascii_digit()) => {
+                Some(FileKind::SaveGame)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Army {
     /// An optional save game header if the army is a save game.
+    #[serde(rename = "save_game_header")]
     pub save_game_header: Option<SaveGameHeader>,
     /// An optional save game footer if the army is a save game.
+    #[serde(rename = "save_game_footer")]
     pub save_game_footer: Option<SaveGameFooter>,
     /// The army's race.
     ///
     /// This is used in multiplayer mode to group armies by race.
+    #[serde(rename = "race")]
     pub race: ArmyRace,
+    #[serde(rename = "unknown1")]
     unknown1: [u8; 3], // always seems to be 0, could be padding
     /// The index of the name to use when army name is empty.
     ///
     /// This is used to display the army name in multiplayer mode when no army
     /// name is set.
+    #[serde(rename = "default_name_index")]
     pub default_name_index: u16,
     /// The name of the army. Displayed in multiplayer mode.
+    #[serde(rename = "name")]
     pub name: String,
     /// There are some bytes after the null-terminated string. Not sure what
     /// they are for.
+    #[serde(rename = "name_remainder")]
     name_remainder: Vec<u8>,
+    #[serde(rename = "small_banner_path")]
     pub small_banner_path: String,
     /// There are some bytes after the null-terminated string. Not sure what
     /// they are for.
+    #[serde(rename = "small_banner_path_remainder")]
     small_banner_path_remainder: Vec<u8>,
+    #[serde(rename = "small_disabled_banner_path")]
     pub small_disabled_banner_path: String,
     /// There are some bytes after the null-terminated string. Not sure what
     /// they are for.
+    #[serde(rename = "small_disabled_banner_path_remainder")]
     small_disabled_banner_path_remainder: Vec<u8>,
+    #[serde(skip)]
     small_disabled_banner_path_remainder_as_u16s: Vec<u16>, // TODO: Remove, debug only.
+    #[serde(skip)]
     small_disabled_banner_path_remainder_as_u32s: Vec<u32>, // TODO: Remove, debug only.
+    #[serde(rename = "large_banner_path")]
     pub large_banner_path: String,
     /// There are some bytes after the null-terminated string. Not sure what
     /// they are for.
+    #[serde(rename = "large_banner_path_remainder")]
     large_banner_path_remainder: Vec<u8>,
+    #[serde(skip)]
     large_banner_path_remainder_as_u16s: Vec<u16>, // TODO: Remove, debug only.
+    #[serde(skip)]
     large_banner_path_remainder_as_u32s: Vec<u32>, // TODO: Remove, debug only.
     /// The amount of gold captured from treasures and earned in the last
     /// battle.
+    #[serde(rename = "last_battle_gold")]
     pub last_battle_gold: u16,
     /// The amount of gold available to the army for buying new units and
     /// reinforcements.
+    #[serde(rename = "gold_in_coffers")]
     pub gold_in_coffers: u16,
     /// A list of magic items in the army's inventory.
     ///
     /// Each magic item is an index into the list of magic items. A value of 1
     /// means the Grudgebringer Sword is equipped in that slot. A value of 0
     /// means the army does not have anything in that slot.
+    #[serde(rename = "magic_items")]
     pub magic_items: Vec<u8>,
+    #[serde(rename = "unknown3")]
     unknown3: Vec<u8>,
+    #[serde(rename = "regiments")]
     pub regiments: Vec<Regiment>,
 }
 
+/// The parts of an army that are stored before its regiments, e.g. for listing
+/// save games without decoding every regiment. See
+/// [`Decoder::decode_header_only`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+pub struct ArmySummary {
+    /// An optional save game header if the army is a save game.
+    pub save_game_header: Option<SaveGameHeader>,
+    /// The army's race. See [`Army::race`].
+    pub race: ArmyRace,
+    /// The index of the name to use when army name is empty. See
+    /// [`Army::default_name_index`].
+    pub default_name_index: u16,
+    /// The name of the army. See [`Army::name`].
+    pub name: String,
+    /// The number of regiments in the army.
+    pub regiment_count: u32,
+    /// The amount of gold available to the army. See [`Army::gold_in_coffers`].
+    pub gold_in_coffers: u16,
+}
+
 impl Army {
+    /// The number of slots in the army's inventory.
+    ///
+    /// The inventory is a fixed-size block in the army header with one byte per
+    /// slot, so this is also the maximum number of magic items. When encoding,
+    /// an army with fewer [`Army::magic_items`] has the remaining slots written
+    /// as empty.
+    pub const MAX_MAGIC_ITEMS: usize = 40;
+
+    /// Decodes an army from the bytes of an army file or a save game.
+    pub fn from_slice(bytes: &[u8]) -> Result<Army, DecodeError> {
+        Decoder::new(bytes).decode()
+    }
+
+    /// Decodes an army from a byte slice, or returns the army already in the
+    /// cache if the same bytes have been decoded before. See [`DecodeCache`].
+    pub fn decode_cached(
+        cache: &DecodeCache<Army>,
+        bytes: &[u8],
+    ) -> Result<Arc<Army>, DecodeError> {
+        cache.get_or_decode(bytes, Army::from_slice)
+    }
+
+    /// Returns a view of the army as a save game, or `None` if the army was not
+    /// decoded from a save game, i.e. it does not have both a save game header
+    /// and footer.
+    pub fn as_save_game(&self) -> Option<SaveGameView<'_>> {
+        Some(SaveGameView {
+            army: self,
+            header: self.save_game_header.as_ref()?,
+            footer: self.save_game_footer.as_ref()?,
+        })
+    }
+
     /// Returns true if the army has any magic items in its inventory.
     pub fn any_magic_items(&self) -> bool {
         self.magic_items.iter().any(|&item| item != 0)
@@ -260,6 +506,186 @@ impl Army {
             .copied()
             .collect()
     }
+
+    /// Returns the army's inventory slots, with `None` for empty slots.
+    ///
+    /// Unlike [`Army::all_magic_items`], the position of each magic item in
+    /// the inventory is kept.
+    pub fn magic_item_slots(&self) -> Vec<Option<u8>> {
+        self.magic_items
+            .iter()
+            .map(|&item| if item == 0 { None } else { Some(item) })
+            .collect()
+    }
+
+    /// Puts the magic item in the given inventory slot, or empties the slot if
+    /// `item` is `None`.
+    ///
+    /// If [`Army::magic_items`] is shorter than `slot`, it is filled up with
+    /// empty slots first. Returns an error if `slot` is not less than
+    /// [`Army::MAX_MAGIC_ITEMS`].
+    pub fn set_magic_item(&mut self, slot: usize, item: Option<u8>) -> Result<(), ItemError> {
+        if slot >= Army::MAX_MAGIC_ITEMS {
+            return Err(ItemError::InvalidSlot(slot));
+        }
+        if slot >= self.magic_items.len() {
+            self.magic_items.resize(slot + 1, 0);
+        }
+
+        self.magic_items[slot] = item.unwrap_or(0);
+
+        Ok(())
+    }
+
+    /// Returns the total amount of gold captured by the army's regiments in
+    /// the last battle.
+    pub fn last_battle_captured_gold(&self) -> u32 {
+        self.regiments
+            .iter()
+            .map(|regiment| regiment.gold_captured as u32)
+            .sum()
+    }
+
+    /// Returns the total amount of gold earned by the army's regiments in the
+    /// last battle. See [`Regiment::last_battle_earned_gold`].
+    pub fn last_battle_earned_gold(&self) -> u32 {
+        self.regiments
+            .iter()
+            .map(Regiment::last_battle_earned_gold)
+            .sum()
+    }
+
+    /// Returns a summary of the last battle, aggregated over all of the army's
+    /// regiments.
+    pub fn last_battle_report(&self) -> LastBattleReport {
+        let mut report = LastBattleReport {
+            gold: self.last_battle_gold as u32,
+            earned_gold: self.last_battle_earned_gold(),
+            captured_gold: self.last_battle_captured_gold(),
+            ..Default::default()
+        };
+
+        for regiment in &self.regiments {
+            report.total_kills += regiment.last_battle_stats.kill_count as u32;
+            report.total_losses += regiment.last_battle_stats.unit_killed_count as u32;
+            report.total_experience += regiment.last_battle_stats.experience as u32;
+        }
+
+        report
+    }
+
+    /// Returns the army's regiments that match all of the filter's predicates,
+    /// in roster order.
+    pub fn regiments_matching(&self, filter: RegimentFilter) -> Vec<&Regiment> {
+        self.regiments
+            .iter()
+            .filter(|regiment| filter.matches(regiment))
+            .collect()
+    }
+
+    /// Returns the army's regiments that are temporarily in the army.
+    pub fn temporary_regiments(&self) -> impl Iterator<Item = &Regiment> {
+        self.regiments.iter().filter(|r| r.is_temporary())
+    }
+
+    /// Returns the army's regiments that have departed.
+    pub fn departed_regiments(&self) -> impl Iterator<Item = &Regiment> {
+        self.regiments.iter().filter(|r| r.is_departed())
+    }
+
+    /// Returns the paths of the army's banner sprite sheets, e.g.
+    /// `[BOOKS]\hshield.spr`, skipping any that are empty.
+    ///
+    /// This does not include the sprite sheets of the army's regiments because
+    /// they are referenced by index. See
+    /// [`Army::referenced_sprite_sheet_indices`].
+    pub fn referenced_sprite_paths(&self) -> Vec<String> {
+        [
+            &self.small_banner_path,
+            &self.small_disabled_banner_path,
+            &self.large_banner_path,
+        ]
+        .into_iter()
+        .filter(|path| !path.is_empty())
+        .cloned()
+        .collect()
+    }
+
+    /// Returns the sorted and deduplicated sprite sheet indices used by the
+    /// army's regiments, including their units, leaders and banners.
+    ///
+    /// Each index is an index into the list of sprite sheet file names found
+    /// in ENGREL.EXE.
+    pub fn referenced_sprite_sheet_indices(&self) -> Vec<u16> {
+        let mut indices = self
+            .regiments
+            .iter()
+            .flat_map(|regiment| {
+                [
+                    regiment.unit_profile.sprite_sheet_index,
+                    regiment.leader_profile.sprite_sheet_index,
+                    regiment.banner_sprite_sheet_index,
+                ]
+            })
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+/// A set of predicates to filter an army's regiments by. See
+/// [`Army::regiments_matching`].
+///
+/// Predicates that are `None` are ignored, so the default filter matches every
+/// regiment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegimentFilter {
+    /// Only match regiments of this class.
+    pub class: Option<RegimentClass>,
+    /// Only match regiments that are, or are not, mages.
+    pub is_mage: Option<bool>,
+    /// Only match regiments that are, or are not, deployable.
+    pub deployable: Option<bool>,
+    /// Only match regiments with at least this threat rating.
+    pub min_threat: Option<u8>,
+}
+
+impl RegimentFilter {
+    /// Returns `true` if the regiment matches all of the filter's predicates.
+    pub fn matches(&self, regiment: &Regiment) -> bool {
+        self.class
+            .is_none_or(|class| regiment.unit_profile.class == class)
+            && self
+                .is_mage
+                .is_none_or(|is_mage| regiment.is_mage() == is_mage)
+            && self
+                .deployable
+                .is_none_or(|deployable| regiment.is_deployable() == deployable)
+            && self
+                .min_threat
+                .is_none_or(|min_threat| regiment.threat_rating() >= min_threat)
+    }
+}
+
+/// A summary of an army's last battle. See [`Army::last_battle_report`].
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+pub struct LastBattleReport {
+    /// The number of enemy units killed by the army.
+    pub total_kills: u32,
+    /// The number of the army's units that were killed.
+    pub total_losses: u32,
+    /// The experience gained by the army.
+    pub total_experience: u32,
+    /// The army's stored gold total for the last battle. See
+    /// [`Army::last_battle_gold`].
+    pub gold: u32,
+    /// The gold earned by the army's regiments from experience. See
+    /// [`Army::last_battle_earned_gold`].
+    pub earned_gold: u32,
+    /// The gold captured by the army's regiments in the last battle.
+    pub captured_gold: u32,
 }
 
 bitflags! {
@@ -280,32 +706,105 @@ bitflags! {
     }
 }
 
+impl fmt::Display for ArmyRace {
+    /// Formats the race as e.g. `Empire` or `Greenskins (Multiplayer)`.
+    ///
+    /// [`ArmyRace::EMPIRE`] is 0, so a race without the greenskins or undead
+    /// flag is formatted as `Empire`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.contains(ArmyRace::GREENSKINS) {
+            write!(f, "Greenskins")?;
+        } else if self.contains(ArmyRace::UNDEAD) {
+            write!(f, "Undead")?;
+        } else {
+            write!(f, "Empire")?;
+        }
+
+        if self.contains(ArmyRace::MULTIPLAYER) {
+            write!(f, " (Multiplayer)")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Display, Error)]
+#[display("invalid army race: {value}")]
+pub struct ParseArmyRaceError {
+    pub value: String,
+}
+
+impl FromStr for ArmyRace {
+    type Err = ParseArmyRaceError;
+
+    /// Parses a race in the format produced by [`ArmyRace`]'s `Display`
+    /// implementation, e.g. `Empire` or `Greenskins (Multiplayer)`. Parsing
+    /// is case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseArmyRaceError {
+            value: s.to_string(),
+        };
+
+        let lowercase = s.trim().to_lowercase();
+        let (name, multiplayer) = match lowercase.strip_suffix("(multiplayer)") {
+            Some(name) => (name.trim_end(), true),
+            None => (lowercase.as_str(), false),
+        };
+
+        let mut race = match name {
+            "empire" => ArmyRace::EMPIRE,
+            "greenskins" => ArmyRace::GREENSKINS,
+            "undead" => ArmyRace::UNDEAD,
+            _ => return Err(err()),
+        };
+        if multiplayer {
+            race.insert(ArmyRace::MULTIPLAYER);
+        }
+
+        Ok(race)
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Regiment {
+    #[serde(rename = "flags")]
     pub flags: RegimentFlags,
+    #[serde(rename = "unknown1")]
     unknown1: [u8; 2],
+    #[serde(rename = "id")]
     pub id: u32,
+    #[serde(rename = "mage_class")]
     pub mage_class: MageClass,
     /// The regiment's maximum level of armor.
+    #[serde(rename = "max_armor")]
     pub max_armor: u8,
+    #[serde(rename = "cost")]
     pub cost: u16,
     /// The index into the list of sprite sheet file names found in ENGREL.EXE
     /// for the regiment's banner.
+    #[serde(rename = "banner_sprite_sheet_index")]
     pub banner_sprite_sheet_index: u16,
+    #[serde(rename = "unknown3")]
     unknown3: [u8; 2],
+    #[serde(rename = "attributes")]
     pub attributes: RegimentAttributes,
     /// The profile of the regiment's rank and file units.
+    #[serde(rename = "unit_profile")]
     pub unit_profile: UnitProfile,
+    #[serde(rename = "unknown4")]
     unknown4: u8,
     /// The profile of the regiment's leader unit.
     ///
     /// Some of the fields are not used for leader units.
+    #[serde(rename = "leader_profile")]
     pub leader_profile: UnitProfile,
     /// The leader's 3D head ID.
+    #[serde(rename = "leader_head_id")]
     pub leader_head_id: u16,
 
     /// The stats of the regiment's last battle.
+    #[serde(rename = "last_battle_stats")]
     pub last_battle_stats: LastBattleStats,
 
     /// A number that represents the regiment's total experience.
@@ -315,11 +814,14 @@ pub struct Regiment {
     /// the regiment has a threat level of 2. If experience >= 3000 and <6000
     /// then the regiment has a threat level of 3. If experience >= 6000 then
     /// the regiment has a threat level of 4.
+    #[serde(rename = "total_experience")]
     pub total_experience: u16,
+    #[serde(rename = "duplicate_id")]
     pub duplicate_id: u8,
     /// The regiment's minimum or base level of armor.
     ///
     /// This is displayed as the gold shields in the troop roster.
+    #[serde(rename = "min_armor")]
     pub min_armor: u8,
     /// The spell book that is equipped to the regiment. A spell book is one of
     /// the magic items.
@@ -330,12 +832,14 @@ pub struct Regiment {
     /// A value of 22 means the Bright Book is equipped. A value of 23 means the
     /// Ice Book is equipped. A value of 65535 means the regiment does not have
     /// a spell book slot. Only mages can equip spell books.
+    #[serde(rename = "spell_book")]
     pub spell_book: SpellBook,
     /// A list of magic items that are equipped to the regiment.
     ///
     /// Each magic item is an index into the list of magic items. A value of 1
     /// means the Grudgebringer Sword is equipped in that slot. A value of 65535
     /// means the regiment does not have anything equipped in that slot.
+    #[serde(rename = "magic_items")]
     pub magic_items: [u16; 3],
     /// A list of spells that the regiment can cast.
     ///
@@ -351,15 +855,22 @@ pub struct Regiment {
     ///
     /// See `GAMEDATA/1PBAT/B3_08/B308MRC.ARM` and
     /// `GAMEDATA/1PBAT/B3_08/B308NME.ARM` for an example with non-zero values.
+    #[serde(rename = "spells")]
     pub spells: [u16; 5],
     /// The amount of gold captured by the regiment in the last battle. The
     /// total amount of gold captured by the army can be calculated by summing
     /// the gold captured by each regiment.
+    #[serde(rename = "gold_captured")]
     pub gold_captured: u16,
+    #[serde(rename = "purchased_armor")]
     pub purchased_armor: u8,
+    #[serde(rename = "max_purchasable_armor")]
     pub max_purchasable_armor: u8,
+    #[serde(rename = "repurchased_unit_count")]
     pub repurchased_unit_count: u8,
+    #[serde(rename = "max_purchasable_unit_count")]
     pub max_purchasable_unit_count: u8,
+    #[serde(rename = "book_profile")]
     pub book_profile: [u8; 4],
 }
 
@@ -403,12 +914,34 @@ impl Regiment {
         self.flags.contains(RegimentFlags::MUST_DEPLOY)
     }
 
+    /// Returns `true` if the regiment is temporarily in the army. See
+    /// [`RegimentFlags::TEMPORARY`].
+    pub fn is_temporary(&self) -> bool {
+        self.flags.contains(RegimentFlags::TEMPORARY)
+    }
+
+    /// Returns `true` if the regiment has departed. See
+    /// [`RegimentFlags::DEPARTED`].
+    pub fn is_departed(&self) -> bool {
+        self.flags.contains(RegimentFlags::DEPARTED)
+    }
+
     /// Returns `true` if the regiment is deployable.
     pub fn is_deployable(&self) -> bool {
         self.flags.contains(RegimentFlags::ACTIVE)
             && !self.flags.contains(RegimentFlags::NON_DEPLOYABLE)
     }
 
+    /// Returns `true` if the regiment is an artillery regiment that has been
+    /// marked as non-deployable, e.g. cannons and mortars in an underground
+    /// battle. See [`RegimentFlags::NON_DEPLOYABLE`].
+    ///
+    /// Note: Whether a battle is underground is not stored in the battle
+    /// tabletop, so the flag on the regiment is the only source of truth.
+    pub fn is_artillery_restricted(&self) -> bool {
+        self.unit_profile.class.is_artillery() && self.flags.contains(RegimentFlags::NON_DEPLOYABLE)
+    }
+
     /// Returns the number of units in the regiment that are alive.
     #[inline(always)]
     pub fn alive_unit_count(&self) -> usize {
@@ -440,6 +973,21 @@ impl Regiment {
         self.mage_class != MageClass::None
     }
 
+    /// Returns the amount of gold the regiment earned in the last battle, which
+    /// is 1.5 times the experience it gained, rounded down.
+    ///
+    /// Note: This does not include the gold the regiment captured, see
+    /// [`Regiment::gold_captured`].
+    pub fn last_battle_earned_gold(&self) -> u32 {
+        self.last_battle_stats.experience as u32 * 3 / 2
+    }
+
+    /// Returns the total value of the regiment in gold.
+    #[inline(always)]
+    pub fn total_value(&self) -> u16 {
+        self.cost
+    }
+
     /// Returns `true` if the regiment has any magic items equipped.
     pub fn any_magic_items(&self) -> bool {
         self.magic_items.iter().any(|&item| item != 65535)
@@ -453,6 +1001,48 @@ impl Regiment {
             .copied()
             .collect()
     }
+
+    /// Equips the magic item in the given slot, replacing any magic item that
+    /// is already equipped in that slot.
+    ///
+    /// Returns an error if the slot is out of bounds or the regiment has the
+    /// [`RegimentAttributes::NO_ITEM_SLOTS`] attribute. The game itself does
+    /// not stop items from being assigned to such regiments, but they cannot
+    /// be seen or changed in the troop roster.
+    pub fn equip_magic_item(&mut self, slot: usize, item: u16) -> Result<(), ItemError> {
+        if slot >= self.magic_items.len() {
+            return Err(ItemError::InvalidSlot(slot));
+        }
+        if self.attributes.contains(RegimentAttributes::NO_ITEM_SLOTS) {
+            return Err(ItemError::NoItemSlots);
+        }
+
+        self.magic_items[slot] = item;
+
+        Ok(())
+    }
+
+    /// Removes the magic item from the given slot, if any.
+    ///
+    /// Returns an error if the slot is out of bounds.
+    pub fn unequip_magic_item(&mut self, slot: usize) -> Result<(), ItemError> {
+        if slot >= self.magic_items.len() {
+            return Err(ItemError::InvalidSlot(slot));
+        }
+
+        self.magic_items[slot] = 65535;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Display, Error, PartialEq)]
+pub enum ItemError {
+    #[display("invalid magic item slot: {_0}")]
+    #[error(ignore)]
+    InvalidSlot(usize),
+    #[display("regiment has no item slots")]
+    NoItemSlots,
 }
 
 bitflags! {
@@ -528,6 +1118,8 @@ bitflags! {
     }
 }
 
+crate::flags::impl_unknown_bits_set!(RegimentFlags);
+
 #[repr(u8)]
 #[derive(
     Clone, Copy, Debug, Default, Deserialize, IntoPrimitive, PartialEq, Serialize, TryFromPrimitive,
@@ -760,6 +1352,78 @@ bitflags! {
     }
 }
 
+crate::flags::impl_unknown_bits_set!(RegimentAttributes);
+
+impl RegimentAttributes {
+    /// Returns a human-readable description for each set attribute, in bit
+    /// order. Unknown attributes are skipped.
+    pub fn descriptions(&self) -> Vec<&'static str> {
+        const DESCRIPTIONS: &[(RegimentAttributes, &str)] = &[
+            (RegimentAttributes::NEVER_ROUTS, "Never routs"),
+            (RegimentAttributes::CAUSES_FEAR, "Causes fear"),
+            (RegimentAttributes::CAUSES_TERROR, "Causes terror"),
+            (RegimentAttributes::ELF_RACE, "Elf"),
+            (RegimentAttributes::GOBLIN_RACE, "Goblin"),
+            (RegimentAttributes::HATES_GREENSKINS, "Hates greenskins"),
+            (
+                RegimentAttributes::NOT_SLOWED_BY_DIFFICULT_TERRAIN,
+                "Not slowed by difficult terrain",
+            ),
+            (
+                RegimentAttributes::IMMUNE_TO_FEAR_CAN_BE_ROUTED,
+                "Immune to fear, but can be routed",
+            ),
+            (RegimentAttributes::REGENERATES_WOUNDS, "Regenerates wounds"),
+            (
+                RegimentAttributes::NEVER_RALLIES_OR_REGROUPS,
+                "Never rallies or regroups",
+            ),
+            (RegimentAttributes::ALWAYS_PURSUES, "Always pursues"),
+            (RegimentAttributes::ENGINE_OF_WAR_RULE, "Engine of war"),
+            (RegimentAttributes::INDESTRUCTIBLE, "Indestructible"),
+            (
+                RegimentAttributes::SUFFERS_ADDITIONAL_WOUNDS,
+                "Suffers additional wounds",
+            ),
+            (
+                RegimentAttributes::INFLICTING_CASUALTY_CAUSES_FEAR,
+                "Inflicting casualties causes fear",
+            ),
+            (RegimentAttributes::COWARDLY, "Cowardly"),
+            (
+                RegimentAttributes::DESTROYED_IF_ROUTED,
+                "Destroyed if routed",
+            ),
+            (RegimentAttributes::FLAMMABLE, "Flammable"),
+            (
+                RegimentAttributes::THREE_SIXTY_DEGREE_VISION,
+                "360 degree vision",
+            ),
+            (RegimentAttributes::SPAWNS_FANATICS, "Spawns fanatics"),
+            (RegimentAttributes::WRAITH_RACE, "Wraith"),
+            (RegimentAttributes::GIANT, "Giant"),
+            (
+                RegimentAttributes::GOBLIN_FLAG_TRADING_POST_MAP_ONLY,
+                "Trading Post goblin",
+            ),
+            (
+                RegimentAttributes::IMPERVIOUS_TO_MAGIC,
+                "Impervious to magic",
+            ),
+            (RegimentAttributes::NEVER_RETREATS, "Never retreats"),
+            (RegimentAttributes::NO_ITEM_SLOTS, "No item slots"),
+            (RegimentAttributes::FANATICS_FLAG, "Fanatic"),
+            (RegimentAttributes::FEARS_ELVES, "Fears elves"),
+        ];
+
+        DESCRIPTIONS
+            .iter()
+            .filter(|(attribute, _)| self.contains(*attribute))
+            .map(|(_, description)| *description)
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct LastBattleStats {
@@ -831,6 +1495,148 @@ pub enum Projectile {
     ScreamingSkullCatapult = 17,
 }
 
+/// The error returned when parsing a regiment enum, e.g. [`RegimentClass`],
+/// from a name that does not match any of its variants.
+#[derive(Debug, Display, Error)]
+#[display("invalid {kind}: {value}")]
+pub struct ParseNameError {
+    /// What was being parsed, e.g. `regiment class`.
+    pub kind: &'static str,
+    pub value: String,
+}
+
+/// Lowercases the name and strips everything but letters and digits, so that
+/// e.g. `Wood Elf`, `wood-elf` and `WoodElf` are treated the same.
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Implements `Display` using the given human-readable names and a
+/// case-insensitive `FromStr` that accepts either the human-readable name or
+/// the variant name.
+macro_rules! impl_display_and_from_str {
+    ($type:ty, $kind:literal, { $($variant:ident => $name:literal),+ $(,)? }) => {
+        impl fmt::Display for $type {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(match self {
+                    $(Self::$variant => $name),+
+                })
+            }
+        }
+
+        impl FromStr for $type {
+            type Err = ParseNameError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let name = normalize_name(s);
+                $(
+                    if name == normalize_name($name) || name == normalize_name(stringify!($variant)) {
+                        return Ok(Self::$variant);
+                    }
+                )+
+                Err(ParseNameError {
+                    kind: $kind,
+                    value: s.to_string(),
+                })
+            }
+        }
+    };
+}
+
+impl_display_and_from_str!(MageClass, "mage class", {
+    None => "None",
+    BaseMage => "Base Mage",
+    OrcAdept => "Orc Adept",
+    AdeptMage => "Adept Mage",
+    MasterMage => "Master Mage",
+});
+
+impl_display_and_from_str!(RegimentClass, "regiment class", {
+    None => "None",
+    HumanInfantryman => "Human Infantryman",
+    WoodElfInfantryman => "Wood Elf Infantryman",
+    DwarfInfantryman => "Dwarf Infantryman",
+    NightGoblinInfantryman => "Night Goblin Infantryman",
+    OrcInfantryman => "Orc Infantryman",
+    UndeadInfantryman => "Undead Infantryman",
+    Townsperson => "Townsperson",
+    Ogre => "Ogre",
+    HumanCavalryman => "Human Cavalryman",
+    OrcCavalryman => "Orc Cavalryman",
+    UndeadCavalryman => "Undead Cavalryman",
+    HumanArcher => "Human Archer",
+    WoodElfArcher => "Wood Elf Archer",
+    NightGoblinArcher => "Night Goblin Archer",
+    OrcArcher => "Orc Archer",
+    SkeletonArcher => "Skeleton Archer",
+    HumanArtilleryUnit => "Human Artillery Unit",
+    OrcArtilleryUnit => "Orc Artillery Unit",
+    UndeadArtilleryUnit => "Undead Artillery Unit",
+    HumanMage => "Human Mage",
+    NightGoblinShaman => "Night Goblin Shaman",
+    OrcShaman => "Orc Shaman",
+    EvilMage => "Evil Mage",
+    DreadKing => "Dread King",
+    Monster => "Monster",
+    UndeadChariot => "Undead Chariot",
+    Fanatic => "Fanatic",
+    Unknown1 => "Unknown 1",
+});
+
+impl_display_and_from_str!(RegimentType, "regiment type", {
+    Unknown => "Unknown",
+    Infantryman => "Infantry",
+    Cavalryman => "Cavalry",
+    Archer => "Archer",
+    ArtilleryUnit => "Artillery",
+    Mage => "Mage",
+    Monster => "Monster",
+    Chariot => "Chariot",
+});
+
+impl_display_and_from_str!(RegimentRace, "regiment race", {
+    Human => "Human",
+    WoodElf => "Wood Elf",
+    Dwarf => "Dwarf",
+    NightGoblin => "Night Goblin",
+    Orc => "Orc",
+    Undead => "Undead",
+    Townsfolk => "Townsfolk",
+});
+
+impl_display_and_from_str!(RegimentMount, "mount", {
+    None => "None",
+    Horse => "Horse",
+    Boar => "Boar",
+});
+
+impl_display_and_from_str!(Weapon, "weapon", {
+    None => "None",
+    BasicHandWeapon => "Basic Hand Weapon",
+    TwoHandedWeapon => "Two-Handed Weapon",
+    Polearm => "Polearm",
+    Flail => "Flail",
+    WightBlade => "Wight Blade",
+});
+
+impl_display_and_from_str!(Projectile, "projectile", {
+    None => "None",
+    ShortBow => "Short Bow",
+    NormalBow => "Normal Bow",
+    ElvenBow => "Elven Bow",
+    Crossbow => "Crossbow",
+    Pistol => "Pistol",
+    Cannon => "Cannon",
+    Mortar => "Mortar",
+    SteamTankCannon => "Steam Tank Cannon",
+    RockLobber => "Rock Lobber",
+    Ballista => "Ballista",
+    ScreamingSkullCatapult => "Screaming Skull Catapult",
+});
+
 #[derive(Debug, Display, Error, From)]
 pub enum DecodeClassError {
     #[error(ignore)]
@@ -886,8 +1692,11 @@ pub struct UnitProfile {
     pub point_value: u8,
     pub projectile: Projectile,
     unknown2: [u8; 4],
-    unknown2_a: u16,      // TODO: Remove, debug only.
-    unknown2_b: u16,      // TODO: Remove, debug only.
+    #[serde(skip)]
+    unknown2_a: u16, // TODO: Remove, debug only.
+    #[serde(skip)]
+    unknown2_b: u16, // TODO: Remove, debug only.
+    #[serde(skip)]
     unknown2_as_u32: u32, // TODO: Remove, debug only.
 }
 
@@ -915,6 +1724,489 @@ mod tests {
         path::{Path, PathBuf},
     };
 
+    #[test]
+    fn test_serialized_field_names() {
+        let mut army_fields = vec![
+            "save_game_header",
+            "save_game_footer",
+            "race",
+            "unknown1",
+            "default_name_index",
+            "name",
+            "name_remainder",
+            "small_banner_path",
+            "small_banner_path_remainder",
+            "small_disabled_banner_path",
+            "small_disabled_banner_path_remainder",
+            "large_banner_path",
+            "large_banner_path_remainder",
+            "last_battle_gold",
+            "gold_in_coffers",
+            "magic_items",
+            "unknown3",
+            "regiments",
+        ];
+        army_fields.sort();
+        assert_eq!(crate::serialized_field_names(&Army::default()), army_fields);
+
+        let mut regiment_fields = vec![
+            "flags",
+            "unknown1",
+            "id",
+            "mage_class",
+            "max_armor",
+            "cost",
+            "banner_sprite_sheet_index",
+            "unknown3",
+            "attributes",
+            "unit_profile",
+            "unknown4",
+            "leader_profile",
+            "leader_head_id",
+            "last_battle_stats",
+            "total_experience",
+            "duplicate_id",
+            "min_armor",
+            "spell_book",
+            "magic_items",
+            "spells",
+            "gold_captured",
+            "purchased_armor",
+            "max_purchasable_armor",
+            "repurchased_unit_count",
+            "max_purchasable_unit_count",
+            "book_profile",
+        ];
+        regiment_fields.sort();
+        assert_eq!(
+            crate::serialized_field_names(&Regiment::default()),
+            regiment_fields
+        );
+    }
+
+    #[test]
+    fn test_regiment_attributes_descriptions() {
+        let attributes = RegimentAttributes::CAUSES_FEAR
+            | RegimentAttributes::UNKNOWN_FLAG_2
+            | RegimentAttributes::FLAMMABLE;
+
+        assert_eq!(attributes.descriptions(), vec!["Causes fear", "Flammable"]);
+        assert!(RegimentAttributes::NONE.descriptions().is_empty());
+    }
+
+    #[test]
+    fn test_army_last_battle_report() {
+        fn make_regiment(kill_count: u16, unit_killed_count: u16, gold_captured: u16) -> Regiment {
+            Regiment {
+                last_battle_stats: LastBattleStats {
+                    unit_killed_count,
+                    kill_count,
+                    experience: kill_count * 2,
+                    ..Default::default()
+                },
+                gold_captured,
+                ..Default::default()
+            }
+        }
+
+        let army = Army {
+            last_battle_gold: 500,
+            regiments: vec![make_regiment(10, 2, 100), make_regiment(5, 3, 50)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            army.last_battle_report(),
+            LastBattleReport {
+                total_kills: 15,
+                total_losses: 5,
+                total_experience: 30,
+                gold: 500,
+                earned_gold: 45,
+                captured_gold: 150,
+            }
+        );
+    }
+
+    #[test]
+    fn test_army_regiments_matching() {
+        fn make_regiment(
+            id: u32,
+            class: RegimentClass,
+            mage_class: MageClass,
+            flags: RegimentFlags,
+            point_value: u8,
+        ) -> Regiment {
+            Regiment {
+                id,
+                flags,
+                mage_class,
+                unit_profile: UnitProfile {
+                    class,
+                    point_value,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }
+
+        let army = Army {
+            regiments: vec![
+                make_regiment(
+                    1,
+                    RegimentClass::HumanCavalryman,
+                    MageClass::None,
+                    RegimentFlags::ACTIVE,
+                    20,
+                ),
+                make_regiment(
+                    2,
+                    RegimentClass::HumanMage,
+                    MageClass::BaseMage,
+                    RegimentFlags::ACTIVE,
+                    0,
+                ),
+                make_regiment(
+                    3,
+                    RegimentClass::HumanCavalryman,
+                    MageClass::None,
+                    RegimentFlags::NONE,
+                    8,
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let ids = |filter: RegimentFilter| {
+            army.regiments_matching(filter)
+                .iter()
+                .map(|regiment| regiment.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(RegimentFilter::default()), vec![1, 2, 3]);
+        assert_eq!(
+            ids(RegimentFilter {
+                class: Some(RegimentClass::HumanCavalryman),
+                ..Default::default()
+            }),
+            vec![1, 3]
+        );
+        assert_eq!(
+            ids(RegimentFilter {
+                is_mage: Some(true),
+                ..Default::default()
+            }),
+            vec![2]
+        );
+        assert_eq!(
+            ids(RegimentFilter {
+                deployable: Some(true),
+                min_threat: Some(2),
+                ..Default::default()
+            }),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_army_temporary_and_departed_regiments() {
+        let regiment = |id, flags| Regiment {
+            id,
+            flags,
+            ..Default::default()
+        };
+
+        let army = Army {
+            regiments: vec![
+                regiment(1, RegimentFlags::ACTIVE),
+                regiment(2, RegimentFlags::ACTIVE | RegimentFlags::TEMPORARY),
+                regiment(3, RegimentFlags::DEPARTED),
+            ],
+            ..Default::default()
+        };
+
+        let ids = |regiments: Vec<&Regiment>| regiments.iter().map(|r| r.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(army.temporary_regiments().collect()), vec![2]);
+        assert_eq!(ids(army.departed_regiments().collect()), vec![3]);
+    }
+
+    #[test]
+    fn test_army_referenced_sprites() {
+        fn make_regiment(unit: u16, leader: u16, banner: u16) -> Regiment {
+            Regiment {
+                unit_profile: UnitProfile {
+                    sprite_sheet_index: unit,
+                    ..Default::default()
+                },
+                leader_profile: UnitProfile {
+                    sprite_sheet_index: leader,
+                    ..Default::default()
+                },
+                banner_sprite_sheet_index: banner,
+                ..Default::default()
+            }
+        }
+
+        let army = Army {
+            small_banner_path: "[BOOKS]\\hshield.spr".to_string(),
+            large_banner_path: "[BOOKS]\\hlshield.spr".to_string(),
+            regiments: vec![make_regiment(4, 5, 20), make_regiment(4, 6, 21)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            army.referenced_sprite_paths(),
+            vec!["[BOOKS]\\hshield.spr", "[BOOKS]\\hlshield.spr"]
+        );
+        assert_eq!(
+            army.referenced_sprite_sheet_indices(),
+            vec![4, 5, 6, 20, 21]
+        );
+    }
+
+    #[test]
+    fn test_save_game_footer_referenced_image_paths() {
+        let animation = |enabled, path: &str| CutsceneAnimation {
+            enabled,
+            path: path.to_string(),
+            ..Default::default()
+        };
+
+        let footer = SaveGameFooter {
+            background_image_path: Some("[PICTURES]\\m_empn.bmp".to_string()),
+            cutscene_animations: vec![
+                animation(true, "[SPRITES]\\m_empbi1.spr"),
+                animation(false, "[SPRITES]\\m_empbi2.spr"),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            footer.referenced_image_paths(),
+            vec!["[PICTURES]\\m_empn.bmp", "[SPRITES]\\m_empbi1.spr"]
+        );
+    }
+
+    #[test]
+    fn test_save_game_header_campaign_summary() {
+        let header = SaveGameHeader {
+            bogenhafen_mission: true,
+            goblin_camp_or_ragnar: true,
+            ragnar_mission_pre_battle: true,
+            carstein_defeated: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            header.completed_missions(),
+            vec!["Bogenhafen completed", "Manfred von Carstein defeated"]
+        );
+
+        let decisions = header.decisions();
+        assert!(decisions.contains(&("Attacked the Goblin Camp or helped Ragnar", true)));
+        assert!(decisions.contains(&("Agreed to help Ragnar", true)));
+        assert!(decisions.contains(&("Met King Orion", false)));
+
+        assert!(SaveGameHeader::default().completed_missions().is_empty());
+    }
+
+    #[test]
+    fn test_army_decode_cached() {
+        let army = Army {
+            magic_items: vec![0; 40],
+            unknown3: vec![0; 2],
+            ..Default::default()
+        };
+        let mut bytes = Vec::new();
+        Encoder::new(&mut bytes).encode(&army).unwrap();
+
+        let cache = DecodeCache::new();
+        let a = Army::decode_cached(&cache, &bytes).unwrap();
+        let b = Army::decode_cached(&cache, &bytes).unwrap();
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(Army::decode_cached(&cache, &bytes[..4]).is_err());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_army_as_save_game() {
+        let mut army = Army {
+            regiments: vec![Regiment::default()],
+            ..Default::default()
+        };
+
+        assert!(army.as_save_game().is_none());
+
+        army.save_game_header = Some(SaveGameHeader {
+            display_name: "Foo".to_string(),
+            ..Default::default()
+        });
+
+        // A footer is also required.
+        assert!(army.as_save_game().is_none());
+
+        army.save_game_footer = Some(SaveGameFooter::default());
+
+        let save_game = army.as_save_game().unwrap();
+        assert_eq!(save_game.display_name(), "Foo");
+        assert_eq!(save_game.regiments().len(), 1);
+    }
+
+    #[test]
+    fn test_file_kind_from_path() {
+        assert_eq!(
+            FileKind::from_path(Path::new("PLYR_ALG.ARM")),
+            Some(FileKind::Arm)
+        );
+        assert_eq!(
+            FileKind::from_path(Path::new("foo.aud")),
+            Some(FileKind::Aud)
+        );
+        assert_eq!(
+            FileKind::from_path(Path::new("foo.ARE")),
+            Some(FileKind::Are)
+        );
+        assert_eq!(
+            FileKind::from_path(Path::new("SAVEGAME.000")),
+            Some(FileKind::SaveGame)
+        );
+        assert_eq!(FileKind::from_path(Path::new("foo.BTB")), None);
+        assert_eq!(FileKind::from_path(Path::new("foo")), None);
+    }
+
+    #[test]
+    fn test_army_magic_item_slots() {
+        let mut army = Army {
+            magic_items: vec![0, 1, 0, 7],
+            ..Default::default()
+        };
+
+        assert_eq!(army.magic_item_slots(), vec![None, Some(1), None, Some(7)]);
+
+        army.set_magic_item(0, Some(3)).unwrap();
+        army.set_magic_item(1, None).unwrap();
+
+        assert_eq!(army.magic_item_slots(), vec![Some(3), None, None, Some(7)]);
+        assert_eq!(army.magic_items, vec![3, 0, 0, 7]);
+
+        assert_eq!(
+            army.set_magic_item(Army::MAX_MAGIC_ITEMS, Some(1)),
+            Err(ItemError::InvalidSlot(Army::MAX_MAGIC_ITEMS))
+        );
+
+        let mut army = Army::default();
+        army.set_magic_item(2, Some(5)).unwrap();
+        assert_eq!(army.magic_items, vec![0, 0, 5]);
+    }
+
+    #[test]
+    fn test_army_race_display_and_parse() {
+        let races = [
+            (ArmyRace::EMPIRE, "Empire"),
+            (ArmyRace::MULTIPLAYER, "Empire (Multiplayer)"),
+            (ArmyRace::GREENSKINS, "Greenskins"),
+            (
+                ArmyRace::GREENSKINS | ArmyRace::MULTIPLAYER,
+                "Greenskins (Multiplayer)",
+            ),
+            (ArmyRace::UNDEAD, "Undead"),
+            (
+                ArmyRace::UNDEAD | ArmyRace::MULTIPLAYER,
+                "Undead (Multiplayer)",
+            ),
+        ];
+
+        for (race, s) in races {
+            assert_eq!(race.to_string(), s);
+            assert_eq!(s.parse::<ArmyRace>().unwrap(), race);
+        }
+
+        assert_eq!(
+            "greenskins (multiplayer)".parse::<ArmyRace>().unwrap(),
+            ArmyRace::GREENSKINS | ArmyRace::MULTIPLAYER
+        );
+        assert!("Dwarfs".parse::<ArmyRace>().is_err());
+    }
+
+    #[test]
+    fn test_regiment_enums_display_and_parse() {
+        assert_eq!(RegimentClass::WoodElfArcher.to_string(), "Wood Elf Archer");
+        assert_eq!(
+            "wood elf archer".parse::<RegimentClass>().unwrap(),
+            RegimentClass::WoodElfArcher
+        );
+        assert_eq!(
+            "WoodElfArcher".parse::<RegimentClass>().unwrap(),
+            RegimentClass::WoodElfArcher
+        );
+        assert_eq!(RegimentType::Cavalryman.to_string(), "Cavalry");
+        assert_eq!(
+            "cavalry".parse::<RegimentType>().unwrap(),
+            RegimentType::Cavalryman
+        );
+        assert_eq!(
+            "CAVALRYMAN".parse::<RegimentType>().unwrap(),
+            RegimentType::Cavalryman
+        );
+        assert_eq!(
+            "two handed weapon".parse::<Weapon>().unwrap(),
+            Weapon::TwoHandedWeapon
+        );
+        assert_eq!(MageClass::MasterMage.to_string(), "Master Mage");
+        assert_eq!(RegimentRace::NightGoblin.to_string(), "Night Goblin");
+        assert_eq!(RegimentMount::Boar.to_string(), "Boar");
+        assert_eq!(
+            Projectile::ScreamingSkullCatapult.to_string(),
+            "Screaming Skull Catapult"
+        );
+
+        let err = "dragon".parse::<RegimentClass>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid regiment class: dragon");
+    }
+
+    #[test]
+    fn test_regiment_equip_magic_item() {
+        let mut regiment = Regiment {
+            magic_items: [65535; 3],
+            ..Default::default()
+        };
+
+        assert_eq!(regiment.equip_magic_item(1, 5), Ok(()));
+        assert_eq!(regiment.magic_items, [65535, 5, 65535]);
+        assert_eq!(
+            regiment.equip_magic_item(3, 5),
+            Err(ItemError::InvalidSlot(3))
+        );
+
+        assert_eq!(regiment.unequip_magic_item(1), Ok(()));
+        assert_eq!(regiment.magic_items, [65535; 3]);
+        assert_eq!(
+            regiment.unequip_magic_item(3),
+            Err(ItemError::InvalidSlot(3))
+        );
+
+        regiment.attributes = RegimentAttributes::NO_ITEM_SLOTS;
+        assert_eq!(regiment.equip_magic_item(0, 5), Err(ItemError::NoItemSlots));
+    }
+
+    #[test]
+    fn test_regiment_gold_valuation() {
+        let regiment = Regiment {
+            cost: 1200,
+            last_battle_stats: LastBattleStats {
+                experience: 25,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(regiment.last_battle_earned_gold(), 37);
+        assert_eq!(regiment.total_value(), 1200);
+    }
+
     #[test]
     fn test_regiment_threat_rating() {
         fn make_regiment(point_value: u8) -> Regiment {
@@ -935,6 +2227,35 @@ mod tests {
         assert_eq!(make_regiment(31).threat_rating(), 4);
     }
 
+    #[test]
+    fn test_regiment_is_artillery_restricted() {
+        fn make_regiment(class: RegimentClass, flags: RegimentFlags) -> Regiment {
+            Regiment {
+                flags,
+                unit_profile: UnitProfile {
+                    class,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }
+
+        let non_deployable = RegimentFlags::ACTIVE | RegimentFlags::NON_DEPLOYABLE;
+
+        assert!(
+            make_regiment(RegimentClass::HumanArtilleryUnit, non_deployable)
+                .is_artillery_restricted()
+        );
+        assert!(
+            !make_regiment(RegimentClass::HumanArtilleryUnit, RegimentFlags::ACTIVE)
+                .is_artillery_restricted()
+        );
+        assert!(
+            !make_regiment(RegimentClass::HumanInfantryman, non_deployable)
+                .is_artillery_restricted()
+        );
+    }
+
     #[test]
     fn test_regiment_class_is_infantry() {
         assert!(RegimentClass::HumanInfantryman.is_infantry());
@@ -1039,6 +2360,107 @@ mod tests {
         assert!(RegimentClass::Townsperson.is_townsperson());
     }
 
+    #[test]
+    fn test_encode_string_too_long() {
+        let army = Army {
+            name: "A".repeat(32),
+            ..Default::default()
+        };
+
+        let mut encoded_bytes = Vec::new();
+        assert!(matches!(
+            Encoder::new(&mut encoded_bytes).encode(&army),
+            Err(EncodeError::StringTooLong {
+                field: "name",
+                max: 32
+            })
+        ));
+
+        let army = Army {
+            name: "A".repeat(31),
+            ..Default::default()
+        };
+
+        let mut encoded_bytes = Vec::new();
+        Encoder::new(&mut encoded_bytes).encode(&army).unwrap();
+        assert_eq!(&encoded_bytes[18..50], [&[b'A'; 31][..], &[0]].concat());
+    }
+
+    #[test]
+    fn test_encode_longer_string_drops_remainder() {
+        let army = Army {
+            name: "Grudgebringers".to_string(),
+            name_remainder: vec![b'x'; 20],
+            ..Default::default()
+        };
+
+        let mut encoded_bytes = Vec::new();
+        Encoder::new(&mut encoded_bytes).encode(&army).unwrap();
+
+        // The remainder is truncated to the space left after the string.
+        assert_eq!(
+            &encoded_bytes[18..50],
+            [&b"Grudgebringers\0"[..], &[b'x'; 17]].concat()
+        );
+    }
+
+    #[test]
+    fn test_encode_too_many_magic_items() {
+        let army = Army {
+            magic_items: vec![0; Army::MAX_MAGIC_ITEMS + 1],
+            ..Default::default()
+        };
+
+        let mut encoded_bytes = Vec::new();
+        assert!(matches!(
+            Encoder::new(&mut encoded_bytes).encode(&army),
+            Err(EncodeError::TooManyMagicItems(41))
+        ));
+    }
+
+    #[test]
+    fn test_encode_pads_magic_items() {
+        let army = Army {
+            magic_items: vec![1, 2],
+            ..Default::default()
+        };
+
+        let mut encoded_bytes = Vec::new();
+        Encoder::new(&mut encoded_bytes).encode(&army).unwrap();
+
+        assert_eq!(encoded_bytes.len(), decoder::HEADER_SIZE_BYTES);
+        assert_eq!(&encoded_bytes[150..152], [1, 2]);
+        assert_eq!(&encoded_bytes[152..], [0; 40]);
+
+        let decoded = Army::from_slice(&encoded_bytes).unwrap();
+        assert_eq!(decoded.magic_items.len(), Army::MAX_MAGIC_ITEMS);
+        assert_eq!(&decoded.magic_items[..3], [1, 2, 0]);
+    }
+
+    #[test]
+    fn test_encode_normalized() {
+        let army = Army {
+            unknown1: [1, 2, 3],
+            name: "Grudgebringers".to_string(),
+            name_remainder: vec![b'X'; 17],
+            ..Default::default()
+        };
+
+        let mut encoded_bytes = Vec::new();
+        Encoder::new(&mut encoded_bytes).encode(&army).unwrap();
+        assert_eq!(&encoded_bytes[13..16], [1, 2, 3]);
+        assert_eq!(&encoded_bytes[33..50], [b'X'; 17]);
+
+        let mut encoded_bytes = Vec::new();
+        Encoder::new(&mut encoded_bytes)
+            .normalized()
+            .encode(&army)
+            .unwrap();
+        assert_eq!(&encoded_bytes[13..16], [0, 0, 0]);
+        assert_eq!(&encoded_bytes[18..33], b"Grudgebringers\0");
+        assert_eq!(&encoded_bytes[33..50], [0; 17]);
+    }
+
     fn roundtrip_test(original_bytes: &[u8], army: &Army) {
         let mut encoded_bytes = Vec::new();
         Encoder::new(&mut encoded_bytes).encode(army).unwrap();
@@ -1072,15 +2494,10 @@ mod tests {
 
     #[test]
     fn test_decode_plyr_alg() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PARM",
-            "PLYR_ALG.ARM",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PARM")
+            .join("PLYR_ALG.ARM");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -1095,16 +2512,11 @@ mod tests {
 
     #[test]
     fn test_decode_b101mrc() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B1_01",
-            "B101MRC.ARM",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B101MRC.ARM");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -1164,16 +2576,11 @@ mod tests {
 
     #[test]
     fn test_decode_b103mrc() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B1_03",
-            "B103MRC.ARM",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_03")
+            .join("B103MRC.ARM");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -1205,6 +2612,10 @@ mod tests {
         let file = File::open(d).unwrap();
         let a = Decoder::new(file).decode().unwrap();
 
+        let from_slice = Army::from_slice(&original_bytes).unwrap();
+        assert_eq!(from_slice.name, a.name);
+        assert_eq!(from_slice.regiments.len(), a.regiments.len());
+
         let save_game_header = a.save_game_header.as_ref().unwrap();
         assert_eq!(save_game_header.display_name, "Grenzgrafschaften - 1026gc");
         assert_eq!(save_game_header.suggested_display_name, "Handelsposten 1");
@@ -1218,9 +2629,56 @@ mod tests {
         assert_eq!(a.regiments[0].last_battle_stats.experience, 46);
         assert_eq!(a.regiments[0].total_experience, 46);
 
+        let save_game = a.as_save_game().unwrap();
+        assert_eq!(save_game.display_name(), "Grenzgrafschaften - 1026gc");
+        assert_eq!(save_game.regiments().len(), a.regiments.len());
+
         roundtrip_test(&original_bytes, &a);
     }
 
+    #[test]
+    fn test_decode_invalid_format() {
+        // Not an army, so it is read as a save game header followed by
+        // something that is not an army header.
+        let bytes = vec![0; 1024];
+
+        assert!(matches!(
+            Army::from_slice(&bytes),
+            Err(DecodeError::InvalidFormat(0))
+        ));
+    }
+
+    #[test]
+    fn test_decode_header_only() {
+        let d: PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "src",
+            "army",
+            "testdata",
+            "save-games",
+            "darkomen.000",
+        ]
+        .iter()
+        .collect();
+
+        let original_bytes = std::fs::read(d).unwrap();
+        let a = Army::from_slice(&original_bytes).unwrap();
+
+        let mut reader = original_bytes.as_slice();
+        let summary = Decoder::new(&mut reader).decode_header_only().unwrap();
+
+        assert_eq!(
+            summary.save_game_header.unwrap().display_name,
+            "Grenzgrafschaften - 1026gc"
+        );
+        assert_eq!(summary.race, a.race);
+        assert_eq!(summary.name, a.name);
+        assert_eq!(summary.regiment_count as usize, a.regiments.len());
+        assert_eq!(summary.gold_in_coffers, a.gold_in_coffers);
+        // Only the save game header and the army header are read.
+        assert_eq!(original_bytes.len() - reader.len(), 504 + 192);
+    }
+
     #[test]
     fn test_decode_save_game_001() {
         let d: PathBuf = [
@@ -1352,13 +2810,7 @@ mod tests {
 
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path().unwrap();
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "armies"]
             .iter()
@@ -1387,13 +2839,10 @@ mod tests {
         }
 
         visit_dirs(&d, &mut |path| {
-            let Some(ext) = path.extension() else {
-                return;
-            };
-            if !(ext.to_string_lossy().to_uppercase() == "ARM"
-                || ext.to_string_lossy().to_uppercase() == "AUD"
-                || ext.to_string_lossy().to_uppercase() == "ARE")
-            {
+            if !matches!(
+                FileKind::from_path(path),
+                Some(FileKind::Arm | FileKind::Aud | FileKind::Are)
+            ) {
                 return;
             }
 
@@ -1422,7 +2871,7 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &army, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &army, crate::ron_config()).unwrap();
         });
     }
 
@@ -1463,12 +2912,7 @@ mod tests {
         }
 
         visit_dirs(&d, &mut |path| {
-            let Some(ext) = path.extension() else {
-                return;
-            };
-            // Skip unless the extension matches \d{3}.
-            let ext = ext.to_string_lossy();
-            if !(ext.len() == 3 && ext.chars().all(|c| c.is_ascii_digit())) {
+            if FileKind::from_path(path) != Some(FileKind::SaveGame) {
                 println!("Skipping {:?}", path.file_name().unwrap());
                 return;
             }
@@ -1480,6 +2924,8 @@ mod tests {
             let file = File::open(path).unwrap();
             let army = Decoder::new(file).decode().unwrap();
 
+            assert!(army.save_game_header.is_some());
+
             let parent_dir = path
                 .components()
                 .collect::<Vec<_>>()
@@ -1497,7 +2943,7 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &army, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &army, crate::ron_config()).unwrap();
 
             roundtrip_test(&original_bytes, &army);
         });
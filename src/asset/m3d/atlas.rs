@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use bevy_math::prelude::*;
+use bevy_render::{mesh::VertexAttributeValues, prelude::*};
+use image::RgbaImage;
+
+use super::mesh::ATTRIBUTE_TEXTURE_INDEX;
+
+/// A texture atlas built from the textures referenced by a single object.
+pub(super) struct TextureAtlas {
+    /// The combined image.
+    pub image: RgbaImage,
+    /// A map of the original texture index to the normalized UV rect of that
+    /// texture in the atlas.
+    pub rects: HashMap<u32, Rect>,
+}
+
+/// The number of pixels around each texture in an atlas that repeat the
+/// texture's edge pixels, so that linear filtering at the edge of a texture
+/// does not sample its neighbor.
+const GUTTER: u32 = 1;
+
+/// Builds a texture atlas from the given textures, keyed by texture index.
+///
+/// The textures are packed left to right in a single row, in texture index
+/// order, each surrounded by a [`GUTTER`]. The original textures are tiny so
+/// there is no need for anything more sophisticated.
+pub(super) fn build_texture_atlas(textures: &[(u32, &RgbaImage)]) -> TextureAtlas {
+    let mut textures = textures.to_vec();
+    textures.sort_by_key(|(texture_index, _)| *texture_index);
+
+    let width = textures
+        .iter()
+        .map(|(_, image)| image.width() + GUTTER * 2)
+        .sum::<u32>();
+    let height = textures
+        .iter()
+        .map(|(_, image)| image.height() + GUTTER * 2)
+        .max()
+        .unwrap_or(0);
+
+    let mut image = RgbaImage::new(width, height);
+    let mut rects = HashMap::new();
+
+    let mut x = 0;
+    for (texture_index, texture) in textures {
+        if texture.width() == 0 || texture.height() == 0 {
+            continue;
+        }
+
+        // Copy the texture with its edge pixels extended into the gutter.
+        for gy in 0..texture.height() + GUTTER * 2 {
+            for gx in 0..texture.width() + GUTTER * 2 {
+                let tx = gx.saturating_sub(GUTTER).min(texture.width() - 1);
+                let ty = gy.saturating_sub(GUTTER).min(texture.height() - 1);
+                image.put_pixel(x + gx, gy, *texture.get_pixel(tx, ty));
+            }
+        }
+
+        rects.insert(
+            texture_index,
+            Rect::new(
+                (x + GUTTER) as f32 / width as f32,
+                GUTTER as f32 / height as f32,
+                (x + GUTTER + texture.width()) as f32 / width as f32,
+                (GUTTER + texture.height()) as f32 / height as f32,
+            ),
+        );
+
+        x += texture.width() + GUTTER * 2;
+    }
+
+    TextureAtlas { image, rects }
+}
+
+/// Remaps the UV0s of `mesh` so that they point into the atlas rects of their
+/// original textures, and resets every texture index to 0.
+///
+/// Textures in an atlas cannot repeat, so this must only be used for objects
+/// whose UVs are all in the `[0, 1]` range, see
+/// [`crate::m3d::Object::needs_wrapping`].
+pub(super) fn remap_uvs_to_atlas(mesh: &mut Mesh, rects: &HashMap<u32, Rect>) {
+    let Some(VertexAttributeValues::Uint32(texture_indices)) =
+        mesh.attribute(ATTRIBUTE_TEXTURE_INDEX).cloned()
+    else {
+        return;
+    };
+
+    if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        for (uv, texture_index) in uvs.iter_mut().zip(texture_indices.iter()) {
+            let Some(rect) = rects.get(texture_index) else {
+                continue;
+            };
+
+            let uv_in_atlas = rect.min + Vec2::from(*uv) * rect.size();
+
+            *uv = [uv_in_atlas.x, uv_in_atlas.y];
+        }
+    }
+
+    mesh.insert_attribute(ATTRIBUTE_TEXTURE_INDEX, vec![0u32; texture_indices.len()]);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_render::{mesh::PrimitiveTopology, render_asset::RenderAssetUsages};
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_build_texture_atlas() {
+        let red = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        let blue = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 255, 255]));
+
+        let atlas = build_texture_atlas(&[(3, &blue), (1, &red)]);
+
+        assert_eq!(atlas.image.dimensions(), (10, 6));
+        // The gutter repeats the edge pixels of each texture.
+        assert_eq!(atlas.image.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(atlas.image.get_pixel(3, 3), &Rgba([255, 0, 0, 255]));
+        assert_eq!(atlas.image.get_pixel(0, 4), &Rgba([0, 0, 0, 0]));
+        assert_eq!(atlas.image.get_pixel(4, 0), &Rgba([0, 0, 255, 255]));
+        assert_eq!(atlas.image.get_pixel(9, 5), &Rgba([0, 0, 255, 255]));
+        assert_eq!(
+            atlas.rects[&1],
+            Rect::new(1. / 10., 1. / 6., 3. / 10., 3. / 6.)
+        );
+        assert_eq!(
+            atlas.rects[&3],
+            Rect::new(5. / 10., 1. / 6., 9. / 10., 5. / 6.)
+        );
+    }
+
+    #[test]
+    fn test_remap_uvs_to_atlas() {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::MAIN_WORLD,
+        )
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_UV_0,
+            vec![[0.5, 0.5], [0.5, 0.25], [0.0, 1.0]],
+        )
+        .with_inserted_attribute(ATTRIBUTE_TEXTURE_INDEX, vec![1u32, 3, 3]);
+
+        let rects = HashMap::from([
+            (1, Rect::new(0., 0., 0.5, 0.5)),
+            (3, Rect::new(0.5, 0., 1., 1.)),
+        ]);
+
+        remap_uvs_to_atlas(&mut mesh, &rects);
+
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0)
+        else {
+            panic!("Mesh does not have a UV0 attribute");
+        };
+        assert_eq!(uvs, &vec![[0.25, 0.25], [0.75, 0.25], [0.5, 1.0]]);
+
+        let Some(VertexAttributeValues::Uint32(texture_indices)) =
+            mesh.attribute(ATTRIBUTE_TEXTURE_INDEX)
+        else {
+            panic!("Mesh does not have a texture index attribute");
+        };
+        assert_eq!(texture_indices, &vec![0, 0, 0]);
+    }
+}
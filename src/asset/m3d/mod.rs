@@ -1,6 +1,8 @@
+mod atlas;
 pub mod mesh;
 
 use std::{
+    collections::BTreeSet,
     io::Cursor,
     marker::PhantomData,
     path::{Path, PathBuf},
@@ -17,12 +19,16 @@ use bevy_reflect::prelude::*;
 use bevy_render::{prelude::*, render_asset::RenderAssetUsages};
 use derive_more::{Display, Error, From};
 use dyn_clone::DynClone;
-use image::Rgba;
+use image::{Rgba, RgbaImage};
 use serde::{Deserialize, Serialize};
 use tracing::*;
 
-use crate::m3d::*;
+use crate::{
+    graphics::{bmp, load_paletted_bmp},
+    m3d::*,
+};
 
+use atlas::*;
 use mesh::*;
 
 pub const EXTENSIONS: &[&str; 4] = &["M3D", "m3d", "M3X", "m3x"];
@@ -130,6 +136,25 @@ pub struct M3dAssetLoaderSettings<MaterialT: Material + std::fmt::Debug> {
     /// Path to the low resolution textures. Relative to the directory of the
     /// M3D file.
     pub low_resolution_textures_path: PathBuf,
+
+    /// If `true`, objects whose faces reference more than one texture have
+    /// those textures combined into a single texture atlas and their UVs
+    /// remapped, so that one material covers the whole object. Objects whose
+    /// textures repeat, see [`Object::needs_wrapping`], are loaded as usual.
+    pub atlas_multi_texture: bool,
+
+    /// If `true`, textures are sampled with nearest filtering instead of linear
+    /// filtering. This keeps the low resolution original textures crisp.
+    pub nearest_filtering: bool,
+
+    /// If `true`, tangents are generated for each mesh. They are only needed by
+    /// normal mapped materials, so this can be turned off for unlit or vertex
+    /// colored models to load them faster.
+    pub generate_tangents: bool,
+
+    /// The names of objects that are not loaded, e.g. collision or helper
+    /// objects that should not be rendered. Names are compared ignoring case.
+    pub exclude_object_names: Vec<String>,
 }
 
 impl<MaterialT: Material + std::fmt::Debug> Default for M3dAssetLoaderSettings<MaterialT> {
@@ -140,6 +165,10 @@ impl<MaterialT: Material + std::fmt::Debug> Default for M3dAssetLoaderSettings<M
             use_low_resolution_textures: false,
             textures_path: PathBuf::new(),
             low_resolution_textures_path: PathBuf::new(),
+            atlas_multi_texture: false,
+            nearest_filtering: false,
+            generate_tangents: true,
+            exclude_object_names: Vec::new(),
         }
     }
 }
@@ -152,6 +181,10 @@ impl<MaterialT: Material + std::fmt::Debug> M3dAssetLoaderSettings<MaterialT> {
             use_low_resolution_textures: false,
             textures_path,
             low_resolution_textures_path,
+            atlas_multi_texture: false,
+            nearest_filtering: false,
+            generate_tangents: true,
+            exclude_object_names: Vec::new(),
         }
     }
 
@@ -159,6 +192,33 @@ impl<MaterialT: Material + std::fmt::Debug> M3dAssetLoaderSettings<MaterialT> {
         self.use_low_resolution_textures = use_low;
         self
     }
+
+    pub fn with_atlas_multi_texture(mut self, atlas_multi_texture: bool) -> Self {
+        self.atlas_multi_texture = atlas_multi_texture;
+        self
+    }
+
+    pub fn with_nearest_filtering(mut self, nearest_filtering: bool) -> Self {
+        self.nearest_filtering = nearest_filtering;
+        self
+    }
+
+    pub fn with_generate_tangents(mut self, generate_tangents: bool) -> Self {
+        self.generate_tangents = generate_tangents;
+        self
+    }
+
+    pub fn with_exclude_object_names(mut self, exclude_object_names: Vec<String>) -> Self {
+        self.exclude_object_names = exclude_object_names;
+        self
+    }
+
+    /// Returns `true` if the object with the given name should not be loaded.
+    fn is_object_excluded(&self, name: &str) -> bool {
+        self.exclude_object_names
+            .iter()
+            .any(|excluded| excluded.eq_ignore_ascii_case(name))
+    }
 }
 
 /// Possible errors that can be produced by [`M3dAssetLoader`].
@@ -213,7 +273,8 @@ impl<MaterialT: Material + std::fmt::Debug> AssetLoader for M3dAssetLoader<Mater
             settings.textures_path.clone()
         };
 
-        self.load_m3d(load_context, textures_path, &m3d).await
+        self.load_m3d(load_context, textures_path, settings, &m3d)
+            .await
     }
 
     fn extensions(&self) -> &[&str] {
@@ -237,6 +298,7 @@ impl<MaterialT: Material + std::fmt::Debug> M3dAssetLoader<MaterialT> {
         self: &M3dAssetLoader<MaterialT>,
         load_context: &mut LoadContext<'_>,
         textures_path: PathBuf,
+        settings: &M3dAssetLoaderSettings<MaterialT>,
         m3d: &M3d,
     ) -> Result<M3dAsset<MaterialT>, M3dAssetLoaderError> {
         let file_path = load_context
@@ -262,8 +324,14 @@ impl<MaterialT: Material + std::fmt::Debug> M3dAssetLoader<MaterialT> {
 
         _span.in_scope(|| debug!("Transparent: {}, animated: {}", transparent, animated));
 
-        let (texture_handles, texture_desciptors) =
-            load_textures(load_context, textures_path, m3d).await?;
+        let (texture_handles, texture_desciptors, texture_images) = load_textures(
+            load_context,
+            textures_path,
+            settings.nearest_filtering,
+            settings.atlas_multi_texture,
+            m3d,
+        )
+        .await?;
 
         let mut meshes = Vec::new();
         for (object_index, object) in m3d.objects.iter().enumerate() {
@@ -274,15 +342,83 @@ impl<MaterialT: Material + std::fmt::Debug> M3dAssetLoader<MaterialT> {
                 continue;
             }
 
+            if settings.is_object_excluded(&object.name) {
+                debug!("Skipping excluded object: {}", object.name);
+                continue;
+            }
+
             let mut mesh = mesh_from_m3d_object(object);
 
-            let generate_tangents_span = info_span!("generate_tangents", name = file_path);
+            if settings.generate_tangents {
+                let generate_tangents_span = info_span!("generate_tangents", name = file_path);
 
-            generate_tangents_span.in_scope(|| {
-                if let Err(err) = mesh.generate_tangents() {
-                    warn!("Could not generate tangents: {}", err);
-                }
-            });
+                generate_tangents_span.in_scope(|| {
+                    if let Err(err) = mesh.generate_tangents() {
+                        warn!("Could not generate tangents: {}", err);
+                    }
+                });
+            }
+
+            let texture_indices = object
+                .faces
+                .iter()
+                .map(|face| face.texture_index as u32)
+                .collect::<BTreeSet<_>>();
+
+            let (object_texture_handles, object_texture_descriptors, material_object) =
+                // Textures in an atlas can't repeat, so objects whose
+                // textures repeat keep their per-texture materials.
+                if settings.atlas_multi_texture
+                    && texture_indices.len() > 1
+                    && !object.needs_wrapping()
+                {
+                    let atlas = build_texture_atlas(
+                        &texture_indices
+                            .iter()
+                            .filter_map(|&i| texture_images.get(i as usize).map(|img| (i, img)))
+                            .collect::<Vec<_>>(),
+                    );
+
+                    remap_uvs_to_atlas(&mut mesh, &atlas.rects);
+
+                    let mut image =
+                        Image::from_dynamic(atlas.image.into(), true, RenderAssetUsages::default());
+                    // Textures in an atlas can't repeat, otherwise the
+                    // neighboring texture would bleed in at the edges.
+                    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                        address_mode_u: ImageAddressMode::ClampToEdge,
+                        address_mode_v: ImageAddressMode::ClampToEdge,
+                        address_mode_w: ImageAddressMode::ClampToEdge,
+                        ..texture_sampler(settings.nearest_filtering)
+                    });
+
+                    let handle = load_context.add_labeled_asset(atlas_label(object), image);
+
+                    let descriptors = texture_indices
+                        .iter()
+                        .filter_map(|&i| texture_desciptors.get(i as usize))
+                        .collect::<Vec<_>>();
+                    let descriptor = M3dTextureDescriptor {
+                        transparent: descriptors.iter().any(|d| d.transparent),
+                        color_keyed: descriptors.iter().any(|d| d.color_keyed),
+                        animated: descriptors.iter().any(|d| d.animated),
+                    };
+
+                    // The material loader only sees the atlas, so every face
+                    // now references the first and only texture.
+                    let mut material_object = object.clone();
+                    for face in material_object.faces.iter_mut() {
+                        face.texture_index = 0;
+                    }
+
+                    (vec![handle], vec![descriptor], material_object)
+                } else {
+                    (
+                        texture_handles.clone(),
+                        texture_desciptors.clone(),
+                        object.clone(),
+                    )
+                };
 
             let object_label = object_label(object);
 
@@ -291,10 +427,10 @@ impl<MaterialT: Material + std::fmt::Debug> M3dAssetLoader<MaterialT> {
             let material = self.material_loader.load(
                 load_context,
                 transparent,
-                texture_handles.clone(),
-                texture_desciptors.clone(),
+                object_texture_handles,
+                object_texture_descriptors,
                 object_index,
-                object,
+                &material_object,
             );
 
             meshes.push(M3dMesh::<MaterialT> {
@@ -311,37 +447,70 @@ impl<MaterialT: Material + std::fmt::Debug> M3dAssetLoader<MaterialT> {
 struct LabeledImage {
     image: Image,
     label: String,
+    /// The processed pixels of the image, kept around so they can be used to
+    /// build texture atlases. `None` if texture atlases are not enabled.
+    pixels: Option<RgbaImage>,
 }
 
+/// Loads the textures of the M3D.
+///
+/// The pixels of each texture are only returned if `keep_pixels` is `true`,
+/// otherwise the returned list of images is empty.
+#[allow(clippy::type_complexity)]
 async fn load_textures(
     load_context: &mut LoadContext<'_>,
     textures_path: PathBuf,
+    nearest_filtering: bool,
+    keep_pixels: bool,
     m3d: &M3d,
-) -> Result<(Vec<Handle<Image>>, Vec<M3dTextureDescriptor>), M3dAssetLoaderError> {
+) -> Result<
+    (
+        Vec<Handle<Image>>,
+        Vec<M3dTextureDescriptor>,
+        Vec<RgbaImage>,
+    ),
+    M3dAssetLoaderError,
+> {
     fn process_loaded_texture(
         load_context: &mut LoadContext,
         handles: &mut Vec<Handle<Image>>,
+        images: &mut Vec<RgbaImage>,
         texture: LabeledImage,
     ) {
         let handle = load_context.add_labeled_asset(texture.label, texture.image);
         handles.push(handle);
+        images.extend(texture.pixels);
     }
 
     let mut texture_handles = Vec::new();
     let mut texture_descriptors = Vec::new();
+    let mut texture_images = Vec::new();
 
     let textures_path = load_context.path().parent().unwrap().join(textures_path);
 
     for descriptor in m3d.texture_descriptors.clone() {
-        let image = load_image(load_context, &descriptor, &textures_path).await?;
-        process_loaded_texture(load_context, &mut texture_handles, image);
+        let image = load_image(
+            load_context,
+            &descriptor,
+            &textures_path,
+            nearest_filtering,
+            keep_pixels,
+        )
+        .await?;
+        process_loaded_texture(
+            load_context,
+            &mut texture_handles,
+            &mut texture_images,
+            image,
+        );
         texture_descriptors.push(M3dTextureDescriptor {
+            transparent: descriptor.is_transparent(),
             color_keyed: descriptor.is_color_keyed(),
-            ..Default::default()
+            animated: descriptor.is_animated(),
         });
     }
 
-    Ok((texture_handles, texture_descriptors))
+    Ok((texture_handles, texture_descriptors, texture_images))
 }
 
 /// Loads a texture as a bevy [`Image`] and returns it together with its label.
@@ -349,16 +518,61 @@ async fn load_image(
     load_context: &mut LoadContext<'_>,
     texture_descriptor: &crate::m3d::M3dTextureDescriptor,
     textures_path: &Path,
+    nearest_filtering: bool,
+    keep_pixels: bool,
 ) -> Result<LabeledImage, M3dAssetLoaderError> {
     let path = textures_path.join(&texture_descriptor.file_name);
 
+    let bytes = load_context
+        .read_asset_bytes(path.clone())
+        .await
+        .map_err(|_| M3dAssetLoaderError::LoadTextureError {
+            dependency: path.clone().into(),
+        })?;
+
+    // Textures are normally 8-bit paletted BMPs, so expand the palette
+    // ourselves to get the color key right. Other formats go through the
+    // generic image loader, but a broken paletted BMP is an error.
+    let dyn_img = match load_paletted_bmp(&bytes, texture_descriptor.is_color_keyed()) {
+        Ok(img) => img.into_rgba8(),
+        Err(
+            bmp::DecodeError::InvalidFormat(_)
+            | bmp::DecodeError::UnsupportedBitDepth(_)
+            | bmp::DecodeError::UnsupportedCompression(_),
+        ) => load_generic_image(load_context, texture_descriptor, &path).await?,
+        Err(_) => {
+            return Err(M3dAssetLoaderError::LoadTextureError {
+                dependency: path.into(),
+            })
+        }
+    };
+
+    let pixels = keep_pixels.then(|| dyn_img.clone());
+
+    let mut image = Image::from_dynamic(dyn_img.into(), true, RenderAssetUsages::default());
+    image.sampler = ImageSampler::Descriptor(texture_sampler(nearest_filtering));
+
+    Ok(LabeledImage {
+        image,
+        label: texture_label(texture_descriptor),
+        pixels,
+    })
+}
+
+/// Loads a texture that is not an 8-bit paletted BMP using the generic image
+/// loader.
+async fn load_generic_image(
+    load_context: &mut LoadContext<'_>,
+    texture_descriptor: &crate::m3d::M3dTextureDescriptor,
+    path: &Path,
+) -> Result<RgbaImage, M3dAssetLoaderError> {
     let loaded = load_context
         .loader()
         .immediate()
-        .load::<Image>(path.clone())
+        .load::<Image>(path.to_path_buf())
         .await
         .map_err(|_| M3dAssetLoaderError::LoadTextureError {
-            dependency: path.clone().into(),
+            dependency: path.to_path_buf().into(),
         })?;
 
     let img = loaded.get();
@@ -367,7 +581,7 @@ async fn load_image(
         .clone()
         .try_into_dynamic()
         .map_err(|_| M3dAssetLoaderError::LoadTextureError {
-            dependency: path.clone().into(),
+            dependency: path.to_path_buf().into(),
         })?
         .into_rgba8();
 
@@ -386,23 +600,23 @@ async fn load_image(
         }
     }
 
-    let mut image = Image::from_dynamic(dyn_img.into(), true, RenderAssetUsages::default());
-    image.sampler = ImageSampler::Descriptor(texture_sampler());
-
-    Ok(LabeledImage {
-        image,
-        label: texture_label(texture_descriptor),
-    })
+    Ok(dyn_img)
 }
 
-fn texture_sampler() -> ImageSamplerDescriptor {
+fn texture_sampler(nearest_filtering: bool) -> ImageSamplerDescriptor {
+    let filter = if nearest_filtering {
+        ImageFilterMode::Nearest
+    } else {
+        ImageFilterMode::Linear
+    };
+
     ImageSamplerDescriptor {
         address_mode_u: ImageAddressMode::Repeat,
         address_mode_v: ImageAddressMode::Repeat,
         address_mode_w: ImageAddressMode::Repeat,
-        mag_filter: ImageFilterMode::Linear,
-        min_filter: ImageFilterMode::Linear,
-        mipmap_filter: ImageFilterMode::Linear,
+        mag_filter: filter,
+        min_filter: filter,
+        mipmap_filter: filter,
         ..Default::default()
     }
 }
@@ -417,6 +631,11 @@ fn object_label(object: &Object) -> String {
     format!("Object{}", object.name)
 }
 
+/// Returns the label for the texture atlas built for the `object`.
+fn atlas_label(object: &Object) -> String {
+    format!("Atlas{}", object.name)
+}
+
 // TODO: Translucency?
 //
 // TODO: Use flags?
@@ -439,3 +658,18 @@ pub fn is_m3d_animated(file_name: &str) -> bool {
 pub fn is_m3d_color_keyed(file_name: &str) -> bool {
     file_name.to_ascii_lowercase().starts_with("_k")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_object_excluded() {
+        let settings = M3dAssetLoaderSettings::<StandardMaterial>::default()
+            .with_exclude_object_names(vec!["Collision".to_string()]);
+
+        assert!(settings.is_object_excluded("collision"));
+        assert!(settings.is_object_excluded("COLLISION"));
+        assert!(!settings.is_object_excluded("collision2"));
+    }
+}
@@ -1,4 +1,6 @@
 use bevy_app::prelude::*;
+#[cfg(feature = "bevy_reflect")]
+use bevy_ecs::reflect::AppTypeRegistry;
 
 use crate::asset::{
     army::ArmyAssetPlugin, battle_tabletop::BattleTabletopAssetPlugin,
@@ -47,6 +49,9 @@ pub struct AssetPlugin;
 
 impl Plugin for AssetPlugin {
     fn build(&self, app: &mut App) {
+        #[cfg(feature = "bevy_reflect")]
+        crate::register_reflect_types(&mut app.world().resource::<AppTypeRegistry>().write());
+
         if !app.is_plugin_added::<AssetPathsPlugin>() {
             app.add_plugins(AssetPathsPlugin);
         }
@@ -3,8 +3,10 @@ use std::{marker::PhantomData, path::PathBuf};
 use bevy_app::prelude::*;
 use bevy_asset::{io::Reader, prelude::*, AssetLoader, LoadContext};
 use bevy_ecs::prelude::*;
+use bevy_math::prelude::*;
 use bevy_pbr::prelude::*;
 use bevy_reflect::prelude::*;
+use bevy_transform::prelude::*;
 use derive_more::derive::{Display, Error, From};
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +16,25 @@ use super::{
     battle_tabletop::*, light::*, lightmap::*, m3d::M3dAsset, paths::*, sound::music_script::*,
 };
 
+impl Instance {
+    /// Returns the instance's transform in Bevy's coordinate space.
+    ///
+    /// This is [`Instance::transform`] with the x-axis and z-axis swapped, the
+    /// same way the M3D mesh loader swaps them for vertex positions, so that
+    /// the instance lines up with the project's models.
+    pub fn to_bevy_transform(&self) -> Transform {
+        let (translation, rotation, scale) = self.transform();
+
+        // Swapping two axes is a reflection, so the rotation axis is swapped
+        // and its direction flipped to keep the same rotation.
+        Transform {
+            translation: Vec3::new(translation.z, translation.y, translation.x),
+            rotation: Quat::from_xyzw(-rotation.z, -rotation.y, -rotation.x, rotation.w),
+            scale: Vec3::new(scale.z, scale.y, scale.x),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct ProjectPlugin<MaterialT: Material + std::fmt::Debug>(PhantomData<MaterialT>);
 
@@ -1,5 +1,7 @@
 use super::*;
+use bitflags::Flags;
 use std::{
+    any::type_name,
     ffi::CStr,
     fmt,
     io::{Error as IoError, Read, Seek},
@@ -25,12 +27,25 @@ impl Int for u32 {
     }
 }
 
+/// The BTB format ID.
+pub(crate) const FORMAT: u32 = 0xbeafeed0;
+
 #[derive(Debug)]
 pub enum DecodeError {
     IoError(IoError),
     InvalidObjectHeaderId(u32),
     InvalidPropertyHeaderId(u32),
     InvalidPropertySize(u32),
+    /// A flag bit whose meaning is not known yet was set. Only returned by a
+    /// strict decoder, see [`Decoder::strict`].
+    UnknownFlagBit {
+        /// The name of the flags type, e.g. `NodeFlags`.
+        ty: &'static str,
+        /// The lowest unknown bit that was set, e.g. 3 for `1 << 3`.
+        bit: u32,
+        /// The offset of the flags value from the start of the file.
+        offset: u64,
+    },
 }
 
 impl std::error::Error for DecodeError {}
@@ -52,28 +67,54 @@ impl fmt::Display for DecodeError {
             DecodeError::InvalidPropertySize(size) => {
                 write!(f, "invalid property size: {}", size)
             }
+            DecodeError::UnknownFlagBit { ty, bit, offset } => {
+                write!(f, "unknown {} bit {} set at offset {}", ty, bit, offset)
+            }
         }
     }
 }
 
+/// Decodes a battle tabletop from a `.BTB` file.
+///
+/// The reader must implement [`Seek`] because the number of regions and nodes
+/// is not stored in the file, so the decoder peeks at the next value to tell
+/// whether another one follows and then seeks back. To decode from a stream,
+/// read it into memory first and use [`BattleTabletop::from_slice`].
 pub struct Decoder<R>
 where
     R: Read + Seek,
 {
     reader: R,
+    strict_flags: bool,
 }
 
 impl<R: Read + Seek> Decoder<R> {
     pub fn new(reader: R) -> Self {
-        Decoder { reader }
+        Decoder {
+            reader,
+            strict_flags: false,
+        }
+    }
+
+    /// Makes the decoder return [`DecodeError::UnknownFlagBit`] if an
+    /// obstacle, region or node has a flag set whose meaning is not known yet,
+    /// i.e. one of the `UNKNOWN_FLAG_*` flags or a bit that has no flag at all.
+    ///
+    /// By default these flags are decoded like any other. Being strict is
+    /// useful when reverse engineering the format to find the files that use
+    /// them.
+    pub fn strict(mut self) -> Self {
+        self.strict_flags = true;
+        self
     }
 
     pub fn decode(&mut self) -> Result<BattleTabletop, DecodeError> {
-        self.check_btb_file_type();
+        self.check_btb_file_type()?;
 
-        let (width, height, player_army, enemy_army, ctl) = self.read_battle_header()?;
+        let (width, height, player_army, enemy_army, ctl, unknown1, unknown2, unknown3) =
+            self.read_battle_header()?;
         let objectives = self.read_objectives()?;
-        let obstacles = self.read_obstacles()?;
+        let (obstacles_unknown1, obstacles) = self.read_obstacles()?;
         let regions = self.read_regions()?;
         let nodes = self.read_nodes()?;
 
@@ -83,18 +124,29 @@ impl<R: Read + Seek> Decoder<R> {
             player_army,
             enemy_army,
             ctl,
+            unknown1,
+            unknown2,
+            unknown3,
             objectives,
+            obstacles_unknown1,
             obstacles,
             regions,
             nodes,
         })
     }
 
-    fn check_btb_file_type(&mut self) {
-        let _ = self.read_object_header(0xbeafeed0);
+    /// Checks the file's magic number, which is stored as the ID of the first
+    /// object header. Returns [`DecodeError::InvalidObjectHeaderId`] if it does
+    /// not match.
+    fn check_btb_file_type(&mut self) -> Result<(), DecodeError> {
+        self.read_object_header(FORMAT)?;
+        Ok(())
     }
 
-    fn read_battle_header(&mut self) -> Result<(u32, u32, String, String, String), DecodeError> {
+    #[allow(clippy::type_complexity)]
+    fn read_battle_header(
+        &mut self,
+    ) -> Result<(u32, u32, String, String, String, String, String, Vec<i32>), DecodeError> {
         let _ = self.read_object_header(1)?;
 
         let width = self.read_int_tuple_property::<i32>(1, 1)?[0] as u32;
@@ -102,11 +154,20 @@ impl<R: Read + Seek> Decoder<R> {
         let player_army = self.read_string_property(1001)?;
         let enemy_army = self.read_string_property(1002)?;
         let ctl = self.read_string_property(1003)?;
-        let _ = self.read_string_property(1004)?;
-        let _ = self.read_string_property(1005)?;
-        let _ = self.read_int_tuple_property::<i32>(9, 2)?;
+        let unknown1 = self.read_string_property(1004)?;
+        let unknown2 = self.read_string_property(1005)?;
+        let unknown3 = self.read_int_tuple_property::<i32>(9, 2)?;
 
-        Ok((width, height, player_army, enemy_army, ctl))
+        Ok((
+            width,
+            height,
+            player_army,
+            enemy_army,
+            ctl,
+            unknown1,
+            unknown2,
+            unknown3,
+        ))
     }
 
     fn read_objectives(&mut self) -> Result<Vec<Objective>, DecodeError> {
@@ -130,10 +191,10 @@ impl<R: Read + Seek> Decoder<R> {
         Ok(objectives)
     }
 
-    fn read_obstacles(&mut self) -> Result<Vec<Obstacle>, DecodeError> {
+    fn read_obstacles(&mut self) -> Result<(i32, Vec<Obstacle>), DecodeError> {
         let size = self.read_object_header(3)?;
 
-        let _unknown = self.read_int_tuple_property::<i32>(8, 1)?[0];
+        let unknown1 = self.read_int_tuple_property::<i32>(8, 1)?[0];
 
         let obstactle_count = (size - 12) / 80;
 
@@ -142,7 +203,7 @@ impl<R: Read + Seek> Decoder<R> {
         for _ in 0..obstactle_count {
             let _ = self.read_property_header(501, 72);
 
-            let flags = self.read_int_tuple_property::<u32>(5, 1)?[0];
+            let flags = self.read_flags::<ObstacleFlags>()?;
             let x = self.read_int_tuple_property::<i32>(1, 1)?[0];
             let y = self.read_int_tuple_property::<i32>(2, 1)?[0];
             let z = self.read_int_tuple_property::<i32>(4, 1)?[0];
@@ -150,7 +211,7 @@ impl<R: Read + Seek> Decoder<R> {
             let dir = self.read_int_tuple_property::<i32>(7, 1)?[0];
 
             obstacles.push(Obstacle {
-                flags: ObstacleFlags::from_bits(flags).expect("obstacle flags should be valid"),
+                flags,
                 position: IVec2::new(x, y),
                 z,
                 radius: radius as u32,
@@ -158,7 +219,7 @@ impl<R: Read + Seek> Decoder<R> {
             });
         }
 
-        Ok(obstacles)
+        Ok((unknown1, obstacles))
     }
 
     fn read_regions(&mut self) -> Result<Vec<Region>, DecodeError> {
@@ -166,9 +227,10 @@ impl<R: Read + Seek> Decoder<R> {
 
         while self.peek_u32()? == 4 {
             let _ = self.read_object_header(4)?;
-            let name = self.read_string_property(1006)?;
-            let flags = self.read_int_tuple_property::<u32>(5, 1)?[0];
-            let _pos = self.read_int_tuple_property::<i32>(10, 2)?;
+            let (name, display_name_residual_bytes) =
+                self.read_string_property_with_residual_bytes(1006)?;
+            let flags = self.read_flags::<RegionFlags>()?;
+            let unknown1 = self.read_int_tuple_property::<i32>(10, 2)?;
 
             let mut line_segments = Vec::new();
 
@@ -183,7 +245,9 @@ impl<R: Read + Seek> Decoder<R> {
 
             regions.push(Region {
                 name,
-                flags: RegionFlags::from_bits(flags).expect("region flags should be valid"),
+                display_name_residual_bytes,
+                flags,
+                unknown1: IVec2::new(unknown1[0], unknown1[1]),
                 line_segments,
             });
         }
@@ -201,7 +265,7 @@ impl<R: Read + Seek> Decoder<R> {
         for _ in 0..node_count {
             let _ = self.read_property_header(503, 96);
 
-            let flags = self.read_int_tuple_property::<u32>(5, 1)?[0];
+            let flags = self.read_flags::<NodeFlags>()?;
             let x = self.read_int_tuple_property::<i32>(1, 1)?[0];
             let y = self.read_int_tuple_property::<i32>(2, 1)?[0];
             let radius = self.read_int_tuple_property::<i32>(6, 1)?[0] as u32;
@@ -211,7 +275,7 @@ impl<R: Read + Seek> Decoder<R> {
             let script_id = self.read_int_tuple_property::<i32>(13, 1)?[0] as u32;
 
             nodes.push(Node {
-                flags: NodeFlags::from_bits(flags).expect("node flags should be valid"),
+                flags,
                 position: IVec2::new(x, y),
                 radius,
                 rotation,
@@ -259,6 +323,25 @@ impl<R: Read + Seek> Decoder<R> {
         Ok(result)
     }
 
+    fn read_flags<T: Flags<Bits = u32>>(&mut self) -> Result<T, DecodeError> {
+        let bits = self.read_int_tuple_property::<u32>(5, 1)?[0];
+        // Bits without a flag are kept so that they are written back as is.
+        let flags = T::from_bits_retain(bits);
+
+        if self.strict_flags {
+            let unknown = crate::flags::unknown_bits_set(&flags).bits() | (bits & !T::all().bits());
+            if unknown != 0 {
+                return Err(DecodeError::UnknownFlagBit {
+                    ty: type_name::<T>().rsplit("::").next().unwrap(),
+                    bit: unknown.trailing_zeros(),
+                    offset: self.reader.stream_position()? - size_of::<u32>() as u64,
+                });
+            }
+        }
+
+        Ok(flags)
+    }
+
     fn read_property_header(
         &mut self,
         expected_id: u32,
@@ -283,16 +366,38 @@ impl<R: Read + Seek> Decoder<R> {
     }
 
     fn read_string_property(&mut self, expected_id: u32) -> Result<String, DecodeError> {
+        let (s, _) = self.read_string_property_with_residual_bytes(expected_id)?;
+        Ok(s)
+    }
+
+    fn read_string_property_with_residual_bytes(
+        &mut self,
+        expected_id: u32,
+    ) -> Result<(String, Option<Vec<u8>>), DecodeError> {
         const MAX_STRING_SIZE_BYTES: usize = 32;
         self.read_property_header(expected_id, MAX_STRING_SIZE_BYTES)?;
 
         let mut buf = vec![0; MAX_STRING_SIZE_BYTES];
         self.reader.read_exact(&mut buf)?;
 
-        Ok(
-            String::from_utf8_lossy(CStr::from_bytes_until_nul(&buf).unwrap().to_bytes())
-                .to_string(),
-        )
+        let s = CStr::from_bytes_until_nul(&buf).unwrap().to_bytes();
+        let residual_bytes = &buf[s.len() + 1..];
+
+        Ok((
+            String::from_utf8_lossy(s).to_string(),
+            if residual_bytes.iter().all(|&b| b == 0) {
+                None
+            } else {
+                Some(
+                    residual_bytes
+                        .iter()
+                        .rposition(|&b| b != 0) // find the last non-zero byte
+                        .map(|pos| &residual_bytes[..=pos]) // include the last non-zero byte
+                        .unwrap_or(residual_bytes)
+                        .to_vec(),
+                )
+            },
+        ))
     }
 
     fn peek_u32(&mut self) -> Result<u32, DecodeError> {
@@ -312,26 +417,115 @@ impl<R: Read + Seek> Decoder<R> {
 mod tests {
     use super::*;
     use crate::project::{self, Project};
-    use image::{DynamicImage, Rgba};
-    use imageproc::{drawing::draw_hollow_rect_mut, rect::Rect};
     use std::{
         ffi::{OsStr, OsString},
         fs::File,
         path::{Path, PathBuf},
     };
 
+    /// Builds a BTB file with a single obstacle with the given flags and no
+    /// objectives, regions or nodes. Also returns the offset of the obstacle's
+    /// flags value.
+    fn btb_with_obstacle_flags(flags: u32) -> (Vec<u8>, u64) {
+        fn header(bytes: &mut Vec<u8>, id: u32, size: usize) {
+            bytes.extend_from_slice(&id.to_le_bytes());
+            bytes.extend_from_slice(&(size as u32).to_le_bytes());
+        }
+        fn int_property(bytes: &mut Vec<u8>, id: u32, values: &[u32]) {
+            header(bytes, id, 8 + values.len() * 4);
+            for value in values {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        let mut bytes = Vec::new();
+        header(&mut bytes, FORMAT, 0);
+
+        header(&mut bytes, 1, 0);
+        int_property(&mut bytes, 1, &[80]);
+        int_property(&mut bytes, 2, &[80]);
+        for id in 1001..=1005 {
+            header(&mut bytes, id, 8 + 32);
+            bytes.extend_from_slice(&[0; 32]);
+        }
+        int_property(&mut bytes, 9, &[0, 0]);
+
+        header(&mut bytes, 2, 0);
+
+        header(&mut bytes, 3, 12 + 80);
+        int_property(&mut bytes, 8, &[0]);
+        header(&mut bytes, 501, 80);
+        let flags_offset = bytes.len() as u64 + 8;
+        int_property(&mut bytes, 5, &[flags]);
+        for id in [1, 2, 4, 6, 7] {
+            int_property(&mut bytes, id, &[0]);
+        }
+
+        header(&mut bytes, 5, 0);
+        int_property(&mut bytes, 8, &[0]);
+
+        (bytes, flags_offset)
+    }
+
+    #[test]
+    fn test_decode_strict() {
+        let flags = ObstacleFlags::IS_ENABLED | ObstacleFlags::UNKNOWN_FLAG_1;
+        let (bytes, flags_offset) = btb_with_obstacle_flags(flags.bits());
+
+        // By default, unknown flags are decoded like any other.
+        let b = BattleTabletop::from_slice(&bytes).unwrap();
+        assert_eq!(b.obstacles.len(), 1);
+        assert_eq!(b.obstacles[0].flags, flags);
+
+        let result = Decoder::new(std::io::Cursor::new(&bytes)).strict().decode();
+        assert!(
+            matches!(
+                result,
+                Err(DecodeError::UnknownFlagBit {
+                    ty: "ObstacleFlags",
+                    bit: 3,
+                    offset,
+                }) if offset == flags_offset
+            ),
+            "{:?}",
+            result
+        );
+
+        // Bits without a flag are kept, and rejected when strict.
+        let (bytes, flags_offset) = btb_with_obstacle_flags(1 << 20);
+
+        let b = BattleTabletop::from_slice(&bytes).unwrap();
+        assert_eq!(b.obstacles[0].flags.bits(), 1 << 20);
+
+        let result = Decoder::new(std::io::Cursor::new(&bytes)).strict().decode();
+        assert!(
+            matches!(
+                result,
+                Err(DecodeError::UnknownFlagBit {
+                    ty: "ObstacleFlags",
+                    bit: 20,
+                    offset,
+                }) if offset == flags_offset
+            ),
+            "{:?}",
+            result
+        );
+
+        // Known flags are accepted.
+        let (bytes, _) = btb_with_obstacle_flags(ObstacleFlags::IS_ENABLED.bits());
+        assert!(Decoder::new(std::io::Cursor::new(&bytes))
+            .strict()
+            .decode()
+            .is_ok());
+    }
+
     #[test]
     fn test_decode_b1_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B1_01",
-            "B1_01.BTB",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.BTB");
 
         let file = File::open(d.clone()).unwrap();
         let b = Decoder::new(file).decode().unwrap();
@@ -361,15 +555,26 @@ mod tests {
         assert_eq!(b.nodes[0].regiment_id, 131);
     }
 
+    #[test]
+    fn test_json_roundtrip_b1_01() {
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.BTB");
+
+        let file = File::open(d).unwrap();
+        let b = Decoder::new(file).decode().unwrap();
+
+        let json = serde_json::to_string(&b).unwrap();
+        let roundtripped: BattleTabletop = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&roundtripped).unwrap(), json);
+    }
+
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path().unwrap();
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "btbs"]
             .iter()
@@ -440,7 +645,7 @@ mod tests {
                 assert!(b.height / 8 <= p.attributes.height);
 
                 // Overlay the battle tabletop on the heightmap image.
-                let img = overlay_battle_tabletop_on_terrain(&p, &b);
+                let img = b.overlay_on_terrain(&p);
                 img.save(
                     output_dir
                         .join(path.file_stem().unwrap())
@@ -461,40 +666,10 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &b, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &b, crate::ron_config()).unwrap();
         });
     }
 
-    /// Note: We know the battle tabletop always fits within the project
-    /// dimensions so we don't need to expand the base image.
-    fn overlay_battle_tabletop_on_terrain(p: &Project, b: &BattleTabletop) -> DynamicImage {
-        // Doesn't matter which heightmap we use, they all have the same
-        // dimensions, but the furniture one has the most detail.
-        let img = p.terrain.furniture_heightmap_image();
-        let mut img_buffer = img.to_rgba8();
-
-        // The image is quite dark, so invert colors just for ease of viewing.
-        for pixel in img_buffer.pixels_mut() {
-            let (r, g, b, a) = (255 - pixel[0], 255 - pixel[1], 255 - pixel[2], pixel[3]); // invert RGB, keep alpha the same
-            *pixel = Rgba([r, g, b, a]);
-        }
-
-        // Pin the rectangle to the top right which is the terrain origin.
-        let start_x = img_buffer.width() as i32 - (b.width / 8) as i32;
-        let start_y = 0; // top edge, so y is 0
-
-        // Draw a hollow rectangle on the base image to show the battle tabletop
-        // dimensions.
-        let rect = Rect::at(start_x, start_y).of_size(b.width / 8, b.height / 8);
-        draw_hollow_rect_mut(&mut img_buffer, rect, Rgba([255, 0, 0, 255]));
-
-        // Now rotate the image 180 degrees to make the origin at the bottom
-        // left which matches the in-game aeiral map view.
-        let img_buffer = image::imageops::rotate180(&img_buffer);
-
-        DynamicImage::ImageRgba8(img_buffer)
-    }
-
     fn append_ext(ext: impl AsRef<OsStr>, path: PathBuf) -> PathBuf {
         let mut os_string: OsString = path.into();
         os_string.push(".");
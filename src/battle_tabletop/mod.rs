@@ -3,8 +3,15 @@ mod decoder;
 #[cfg(feature = "bevy_reflect")]
 use bevy_reflect::prelude::*;
 use bitflags::bitflags;
-use glam::{IVec2, Vec2};
+use glam::{I64Vec2, IVec2, Vec2};
+use image::{DynamicImage, Rgba, RgbaImage};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{
+    army::{Army, Regiment},
+    project::Project,
+};
 
 pub use decoder::{DecodeError, Decoder};
 
@@ -14,26 +21,390 @@ pub use decoder::{DecodeError, Decoder};
 /// the battle tabletop coordinates by the scale.
 pub const SCALE: f32 = 8.;
 
-#[derive(Debug, Clone, Serialize)]
+/// Converts a position in battle tabletop coordinates to world coordinates.
+#[inline]
+pub fn to_world_position(position: IVec2) -> Vec2 {
+    position.as_vec2() / SCALE
+}
+
+/// Converts a position in world coordinates to battle tabletop coordinates.
+///
+/// The position is rounded to the nearest battle tabletop coordinate.
+#[inline]
+pub fn from_world_position(position: Vec2) -> IVec2 {
+    (position * SCALE).round().as_ivec2()
+}
+
+/// Converts a radius in world space to battle tabletop units, rounded to the
+/// nearest unit.
+#[inline]
+fn from_world_radius(radius: f32) -> u32 {
+    (radius * SCALE).round() as u32
+}
+
+/// Pairs each of the army's regiments with the battle tabletop nodes that
+/// belong to it, matching [`Regiment::id`] to [`Node::regiment_id`].
+///
+/// Regiments are returned in roster order. Regiments without any nodes, e.g.
+/// those in the army reserve, are paired with an empty list.
+pub fn join_regiments_and_nodes<'a>(
+    army: &'a Army,
+    btb: &'a BattleTabletop,
+) -> Vec<(&'a Regiment, Vec<&'a Node>)> {
+    army.regiments
+        .iter()
+        .map(|regiment| {
+            let nodes = btb
+                .nodes
+                .iter()
+                .filter(|node| node.regiment_id == regiment.id)
+                .collect();
+            (regiment, nodes)
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct BattleTabletop {
+    #[serde(rename = "width")]
     pub width: u32,
+    #[serde(rename = "height")]
     pub height: u32,
     /// The name of the player's army file, without the extension. E.g.
     /// `b101mrc`.
+    #[serde(rename = "player_army")]
     pub player_army: String,
     /// The name of the enemy's army file, without the extension. E.g.
     /// `b101nme`.
+    #[serde(rename = "enemy_army")]
     pub enemy_army: String,
     /// The name of the CTL file, without the extension. E.g. `B101`.
+    #[serde(rename = "ctl")]
     pub ctl: String,
+    #[serde(rename = "unknown1")]
+    unknown1: String,
+    #[serde(rename = "unknown2")]
+    unknown2: String,
+    #[serde(rename = "unknown3")]
+    unknown3: Vec<i32>,
+    #[serde(rename = "objectives")]
     pub objectives: Vec<Objective>,
+    #[serde(rename = "obstacles_unknown1")]
+    obstacles_unknown1: i32,
+    #[serde(rename = "obstacles")]
     pub obstacles: Vec<Obstacle>,
+    #[serde(rename = "regions")]
     pub regions: Vec<Region>,
+    #[serde(rename = "nodes")]
     pub nodes: Vec<Node>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+impl BattleTabletop {
+    /// Decodes a battle tabletop from the bytes of a `.BTB` file.
+    ///
+    /// This is a shorthand for decoding with a [`Decoder`] over a
+    /// [`std::io::Cursor`], which is needed because the decoder seeks.
+    pub fn from_slice(bytes: &[u8]) -> Result<BattleTabletop, DecodeError> {
+        Decoder::new(std::io::Cursor::new(bytes)).decode()
+    }
+
+    /// Returns the unknown string property that follows the CTL file name.
+    pub fn unknown1(&self) -> &str {
+        &self.unknown1
+    }
+
+    /// Returns the unknown string property that follows [`Self::unknown1`].
+    pub fn unknown2(&self) -> &str {
+        &self.unknown2
+    }
+
+    /// Returns the unknown pair of integers at the end of the battle header.
+    pub fn unknown3(&self) -> &[i32] {
+        &self.unknown3
+    }
+
+    /// Returns the unknown integer at the start of the obstacles block.
+    pub fn obstacles_unknown1(&self) -> i32 {
+        self.obstacles_unknown1
+    }
+
+    /// Returns the objective with the given type, if any.
+    fn objective(&self, typ: i32) -> Option<&Objective> {
+        self.objectives.iter().find(|o| o.typ == typ)
+    }
+
+    /// Returns the ID of the player's regiment that ends the battle in defeat
+    /// if it is lost, or `None` if the battle has no such objective, e.g. in
+    /// multiplayer battles.
+    pub fn critical_regiment_id(&self) -> Option<u32> {
+        self.objective(Objective::CRITICAL_REGIMENT_LOSE_CONDITION_ID)
+            .map(|o| o.val1 as u32)
+    }
+
+    /// Returns the initial orientation of the player's regiments in radians,
+    /// or `None` if the battle has no initial orientation objective.
+    pub fn initial_player_orientation_radians(&self) -> Option<f32> {
+        self.objective(Objective::INITIAL_ORIENTATION_ID)
+            .map(|o| Objective::rotation_radians(o.val1))
+    }
+
+    /// Returns the initial orientation of the enemy's regiments in radians,
+    /// or `None` if the battle has no initial orientation objective.
+    pub fn initial_enemy_orientation_radians(&self) -> Option<f32> {
+        self.objective(Objective::INITIAL_ORIENTATION_ID)
+            .map(|o| Objective::rotation_radians(o.val2))
+    }
+
+    /// Returns the minimum and maximum world coordinates of the battlefield.
+    ///
+    /// The origin of the battle tabletop is at the minimum corner, so the
+    /// minimum is always [`Vec2::ZERO`] and the maximum is the battle
+    /// tabletop's width and height in world coordinates. See
+    /// [`to_world_position`].
+    pub fn world_bounds(&self) -> (Vec2, Vec2) {
+        (Vec2::ZERO, self.world_size())
+    }
+
+    /// Returns the width and height of the battlefield in world coordinates.
+    pub fn world_size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) / SCALE
+    }
+
+    /// Returns the regions that are deployment zones for either player.
+    pub fn deployment_zones(&self) -> Vec<&Region> {
+        self.regions
+            .iter()
+            .filter(|r| r.is_deployment_zone())
+            .collect()
+    }
+
+    /// Returns the regions that are deployment zones for player 1.
+    pub fn player1_deployment_zones(&self) -> Vec<&Region> {
+        self.regions
+            .iter()
+            .filter(|r| r.is_player1_deployment_zone())
+            .collect()
+    }
+
+    /// Returns the regions that are deployment zones for player 2.
+    pub fn player2_deployment_zones(&self) -> Vec<&Region> {
+        self.regions
+            .iter()
+            .filter(|r| r.is_player2_deployment_zone())
+            .collect()
+    }
+
+    /// Returns the polygons of the battle's navmesh in world coordinates, as
+    /// the outer boundaries and the holes in them.
+    ///
+    /// Outer boundaries are the regions flagged with
+    /// [`RegionFlags::IS_BATTLE_BOUNDARY`] and holes are the regions flagged
+    /// with [`RegionFlags::IS_BOUNDARY_REVERSED`]. If a region has both flags,
+    /// it is treated as a hole. Each polygon is returned as in
+    /// [`Region::world_polygon`].
+    pub fn navmesh_polygons(&self) -> (Vec<Vec<Vec2>>, Vec<Vec<Vec2>>) {
+        let mut outer = Vec::new();
+        let mut holes = Vec::new();
+
+        for region in &self.regions {
+            if region.flags.contains(RegionFlags::IS_BOUNDARY_REVERSED) {
+                holes.push(region.world_polygon());
+            } else if region.flags.contains(RegionFlags::IS_BATTLE_BOUNDARY) {
+                outer.push(region.world_polygon());
+            }
+        }
+
+        (outer, holes)
+    }
+
+    /// Checks that the battle tabletop fits the project it is used with.
+    ///
+    /// The scaled down dimensions of the battle tabletop must not be larger
+    /// than the project's dimensions and all obstacles and nodes must be
+    /// within the project's terrain.
+    ///
+    /// Returns all mismatches that were found.
+    pub fn validate_against_project(&self, p: &Project) -> Result<(), Vec<MismatchError>> {
+        let mut errors = Vec::new();
+
+        let (width, height) = (self.width / SCALE as u32, self.height / SCALE as u32);
+        if width > p.attributes.width {
+            errors.push(MismatchError::WidthExceedsProject {
+                width,
+                project_width: p.attributes.width,
+            });
+        }
+        if height > p.attributes.height {
+            errors.push(MismatchError::HeightExceedsProject {
+                height,
+                project_height: p.attributes.height,
+            });
+        }
+
+        let is_within_terrain = |position: Vec2| {
+            (0.0..=p.attributes.width as f32).contains(&position.x)
+                && (0.0..=p.attributes.height as f32).contains(&position.y)
+        };
+
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            if !is_within_terrain(obstacle.world_position()) {
+                errors.push(MismatchError::ObstacleOutOfBounds {
+                    index,
+                    position: obstacle.position,
+                });
+            }
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            if !is_within_terrain(node.world_position()) {
+                errors.push(MismatchError::NodeOutOfBounds {
+                    index,
+                    position: node.position,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns an image of the project's terrain with the battle tabletop's
+    /// dimensions drawn on it as a red rectangle. This is useful for checking
+    /// how the battle tabletop lines up with the terrain.
+    ///
+    /// The terrain's heightmap is quite dark, so its colors are inverted. The
+    /// image is oriented so that the origin is at the bottom left, which
+    /// matches the in-game aerial map view.
+    ///
+    /// Note: The battle tabletop always fits within the project dimensions so
+    /// the image is the same size as the terrain.
+    pub fn overlay_on_terrain(&self, p: &Project) -> DynamicImage {
+        // Doesn't matter which heightmap we use, they all have the same
+        // dimensions, but the furniture one has the most detail.
+        let img = p.terrain.furniture_heightmap_image();
+        let mut img_buffer = img.to_rgba8();
+
+        // The image is quite dark, so invert colors just for ease of viewing.
+        for pixel in img_buffer.pixels_mut() {
+            let (r, g, b, a) = (255 - pixel[0], 255 - pixel[1], 255 - pixel[2], pixel[3]); // invert RGB, keep alpha the same
+            *pixel = Rgba([r, g, b, a]);
+        }
+
+        // Pin the rectangle to the top right which is the terrain origin.
+        let width = self.width / SCALE as u32;
+        let height = self.height / SCALE as u32;
+        let start_x = img_buffer.width() as i32 - width as i32;
+        let start_y = 0; // top edge, so y is 0
+
+        // Draw a hollow rectangle on the base image to show the battle tabletop
+        // dimensions.
+        draw_hollow_rect(
+            &mut img_buffer,
+            (start_x, start_y),
+            (width, height),
+            Rgba([255, 0, 0, 255]),
+        );
+
+        // Now rotate the image 180 degrees to make the origin at the bottom
+        // left which matches the in-game aeiral map view.
+        let img_buffer = image::imageops::rotate180(&img_buffer);
+
+        DynamicImage::ImageRgba8(img_buffer)
+    }
+}
+
+/// Draws the outline of a rectangle with its top left corner at `position`.
+/// Any part of the rectangle outside of the image is not drawn.
+fn draw_hollow_rect(img: &mut RgbaImage, position: (i32, i32), size: (u32, u32), color: Rgba<u8>) {
+    if size.0 == 0 || size.1 == 0 {
+        return;
+    }
+
+    let (left, top) = position;
+    let right = left + size.0 as i32 - 1;
+    let bottom = top + size.1 as i32 - 1;
+
+    let mut put_pixel = |x: i32, y: i32| {
+        if x >= 0 && y >= 0 && (x as u32) < img.width() && (y as u32) < img.height() {
+            img.put_pixel(x as u32, y as u32, color);
+        }
+    };
+
+    for x in left..=right {
+        put_pixel(x, top);
+        put_pixel(x, bottom);
+    }
+    for y in top..=bottom {
+        put_pixel(left, y);
+        put_pixel(right, y);
+    }
+}
+
+/// A mismatch between a battle tabletop and its project. See
+/// [`BattleTabletop::validate_against_project`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MismatchError {
+    WidthExceedsProject { width: u32, project_width: u32 },
+    HeightExceedsProject { height: u32, project_height: u32 },
+    ObstacleOutOfBounds { index: usize, position: IVec2 },
+    NodeOutOfBounds { index: usize, position: IVec2 },
+}
+
+impl std::error::Error for MismatchError {}
+
+impl fmt::Display for MismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MismatchError::WidthExceedsProject {
+                width,
+                project_width,
+            } => write!(
+                f,
+                "scaled width {} exceeds project width {}",
+                width, project_width
+            ),
+            MismatchError::HeightExceedsProject {
+                height,
+                project_height,
+            } => write!(
+                f,
+                "scaled height {} exceeds project height {}",
+                height, project_height
+            ),
+            MismatchError::ObstacleOutOfBounds { index, position } => {
+                write!(f, "obstacle {} out of bounds: {}", index, position)
+            }
+            MismatchError::NodeOutOfBounds { index, position } => {
+                write!(f, "node {} out of bounds: {}", index, position)
+            }
+        }
+    }
+}
+
+/// An error returned by [`Region::from_points`] when there are too few points
+/// to form a closed polygon.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TooFewPointsError {
+    pub count: usize,
+}
+
+impl std::error::Error for TooFewPointsError {}
+
+impl fmt::Display for TooFewPointsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "too few points for a region: {}, expected at least {}",
+            self.count,
+            Region::MIN_POINTS
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Objective {
     pub typ: i32,
@@ -41,7 +412,26 @@ pub struct Objective {
     pub val2: i32,
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+impl Objective {
+    /// The type of the objective that sets the player's regiment that must not
+    /// be lost. `val1` is the ID of the regiment. If the regiment is lost, the
+    /// battle is lost.
+    pub const CRITICAL_REGIMENT_LOSE_CONDITION_ID: i32 = 3;
+
+    /// The type of the objective that sets the initial orientation of the
+    /// regiments. `val1` is the player's orientation and `val2` is the enemy's
+    /// orientation.
+    pub const INITIAL_ORIENTATION_ID: i32 = 7;
+
+    /// Converts an orientation value to radians. Orientation values use the
+    /// same range as [`Node::rotation`], i.e. [0, 512).
+    #[inline]
+    pub fn rotation_radians(value: i32) -> f32 {
+        (value as f32 / 512.0) * std::f32::consts::TAU
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Obstacle {
     pub flags: ObstacleFlags,
@@ -53,14 +443,25 @@ pub struct Obstacle {
 }
 
 impl Obstacle {
+    /// Creates an obstacle at the given position in world coordinates with the
+    /// given radius in world space.
+    ///
+    /// The position and radius are rounded to the nearest battle tabletop
+    /// units. See [`from_world_position`].
+    pub fn from_world(position: Vec2, radius: f32, flags: ObstacleFlags) -> Obstacle {
+        Obstacle {
+            flags,
+            position: from_world_position(position),
+            radius: from_world_radius(radius),
+            ..Default::default()
+        }
+    }
+
     /// Returns the position of the obstacle in the horizontal plane, in world
     /// coordinates.
     #[inline]
     pub fn world_position(&self) -> Vec2 {
-        Vec2::new(
-            self.position.x as f32 / SCALE,
-            self.position.y as f32 / SCALE,
-        )
+        to_world_position(self.position)
     }
 
     /// Returns the radius of the obstacle in world space.
@@ -97,7 +498,9 @@ bitflags! {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+crate::flags::impl_unknown_bits_set!(ObstacleFlags);
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct LineSegment {
     /// The start position of the line segment in the horizontal plane.
@@ -110,13 +513,60 @@ impl LineSegment {
     /// Returns the start position of the line segment in world coordinates.
     #[inline]
     pub fn world_start(&self) -> Vec2 {
-        Vec2::new(self.start.x as f32 / SCALE, self.start.y as f32 / SCALE)
+        to_world_position(self.start)
     }
 
     /// Returns the end position of the line segment in world coordinates.
     #[inline]
     pub fn world_end(&self) -> Vec2 {
-        Vec2::new(self.end.x as f32 / SCALE, self.end.y as f32 / SCALE)
+        to_world_position(self.end)
+    }
+
+    /// Returns the point where the line segment intersects the other line
+    /// segment, rounded to the nearest integer coordinates, or `None` if they
+    /// do not intersect.
+    ///
+    /// If the line segments are collinear and overlap, one of the endpoints in
+    /// the overlap is returned.
+    pub fn intersects(&self, other: &LineSegment) -> Option<IVec2> {
+        let cross = |a: I64Vec2, b: I64Vec2| a.x * b.y - a.y * b.x;
+
+        let p = self.start.as_i64vec2();
+        let r = self.end.as_i64vec2() - p;
+        let q = other.start.as_i64vec2();
+        let s = other.end.as_i64vec2() - q;
+
+        let denominator = cross(r, s);
+        let qp = q - p;
+
+        if denominator == 0 {
+            // The line segments are parallel, so they can only intersect if
+            // they are collinear.
+            if cross(qp, r) != 0 {
+                return None;
+            }
+
+            return [other.start, other.end, self.start, self.end]
+                .into_iter()
+                .find(|point| {
+                    self.is_point_on_line_segment(point) && other.is_point_on_line_segment(point)
+                });
+        }
+
+        let t = cross(qp, s) as f64 / denominator as f64;
+        let u = cross(qp, r) as f64 / denominator as f64;
+
+        if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        Some((p.as_dvec2() + t * r.as_dvec2()).round().as_ivec2())
+    }
+
+    /// Returns the shortest distance from the point to the line segment.
+    pub fn distance_to_point(&self, point: IVec2) -> f32 {
+        let point = point.as_vec2();
+        self.closest_point(point).distance(point)
     }
 
     /// Returns `true` if a point is on a line segment.
@@ -163,21 +613,107 @@ impl LineSegment {
         // The ray intersects if the intersection is to the right of the point.
         intersection_x >= point.x
     }
+
+    /// Returns the point on the line segment that is closest to the given
+    /// point.
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        let start = self.start.as_vec2();
+        let end = self.end.as_vec2();
+
+        let length_squared = start.distance_squared(end);
+        if length_squared == 0. {
+            return start;
+        }
+
+        let t = ((point - start).dot(end - start) / length_squared).clamp(0., 1.);
+        start + t * (end - start)
+    }
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Region {
     pub name: String,
+    /// The original game writes over the existing name with the new name but
+    /// the old bytes are not cleared first. This field is used to store the
+    /// residual bytes, if there are any. If it's `None` then there are no
+    /// residual bytes / all bytes are zero after the null-terminated string. If
+    /// it's `Some`, then it contains the residual bytes, up to, but not
+    /// including, the last nul-terminated string.
+    display_name_residual_bytes: Option<Vec<u8>>,
     pub flags: RegionFlags,
+    unknown1: IVec2,
     pub line_segments: Vec<LineSegment>,
 }
 
 impl Region {
+    /// The minimum number of points needed to form a closed region.
+    pub const MIN_POINTS: usize = 3;
+
+    /// Creates an unnamed region whose boundary connects the given points in
+    /// order, in battle tabletop coordinates.
+    ///
+    /// The boundary is closed automatically by connecting the last point back
+    /// to the first, so the first point should not be repeated at the end.
+    /// The flags are used as is, e.g. [`RegionFlags::IS_CLOSED`] is not set
+    /// unless it is given.
+    pub fn from_points(points: &[IVec2], flags: RegionFlags) -> Result<Region, TooFewPointsError> {
+        if points.len() < Self::MIN_POINTS {
+            return Err(TooFewPointsError {
+                count: points.len(),
+            });
+        }
+
+        let line_segments = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(&start, &end)| LineSegment { start, end })
+            .collect();
+
+        Ok(Region {
+            flags,
+            line_segments,
+            ..Default::default()
+        })
+    }
+
+    /// Returns the residual bytes left over after the region's name, if any.
+    ///
+    /// This is only useful for debugging and reverse engineering the format.
+    pub fn display_name_residual_bytes(&self) -> Option<&[u8]> {
+        self.display_name_residual_bytes.as_deref()
+    }
+
+    /// Returns the unknown pair of integers that follows the region's flags.
+    pub fn unknown1(&self) -> IVec2 {
+        self.unknown1
+    }
+
+    /// Returns the corners of the region's boundary in world coordinates, in
+    /// order.
+    ///
+    /// This is the start of each line segment, so the polygon is implicitly
+    /// closed and the first point is not repeated at the end.
+    pub fn world_polygon(&self) -> Vec<Vec2> {
+        self.line_segments
+            .iter()
+            .map(|line| line.world_start())
+            .collect()
+    }
+
     /// Returns `true` if the region is a deployment zone.
     pub fn is_deployment_zone(&self) -> bool {
+        self.is_player1_deployment_zone() || self.is_player2_deployment_zone()
+    }
+
+    /// Returns `true` if the region is a deployment zone for player 1.
+    pub fn is_player1_deployment_zone(&self) -> bool {
         self.flags.contains(RegionFlags::IS_PLAYER1_DEPLOYMENT_ZONE)
-            || self.flags.contains(RegionFlags::IS_PLAYER2_DEPLOYMENT_ZONE)
+    }
+
+    /// Returns `true` if the region is a deployment zone for player 2.
+    pub fn is_player2_deployment_zone(&self) -> bool {
+        self.flags.contains(RegionFlags::IS_PLAYER2_DEPLOYMENT_ZONE)
     }
 
     /// Returns `true` if the given point is contained within the region.
@@ -198,6 +734,27 @@ impl Region {
         // Odd number of intersections means the point is inside.
         intersections % 2 == 1
     }
+
+    /// Returns the given point if it is contained within the region, otherwise
+    /// the closest point on the region's boundary, rounded to the nearest
+    /// integer coordinates.
+    ///
+    /// This can be used to keep a position within a region, e.g. to snap a
+    /// regiment back into its deployment zone.
+    pub fn nearest_point_inside(&self, point: IVec2) -> IVec2 {
+        if self.is_point_contained(point) {
+            return point;
+        }
+
+        let p = point.as_vec2();
+
+        self.line_segments
+            .iter()
+            .map(|line| line.closest_point(p))
+            .min_by(|a, b| a.distance_squared(p).total_cmp(&b.distance_squared(p)))
+            .map(|closest| closest.round().as_ivec2())
+            .unwrap_or(point)
+    }
 }
 
 bitflags! {
@@ -231,7 +788,9 @@ bitflags! {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+crate::flags::impl_unknown_bits_set!(RegionFlags);
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Node {
     pub flags: NodeFlags,
@@ -256,6 +815,20 @@ pub struct Node {
 }
 
 impl Node {
+    /// Creates a node at the given position in world coordinates with the
+    /// given radius in world space.
+    ///
+    /// The position and radius are rounded to the nearest battle tabletop
+    /// units. See [`from_world_position`].
+    pub fn from_world(position: Vec2, radius: f32, flags: NodeFlags) -> Node {
+        Node {
+            flags,
+            position: from_world_position(position),
+            radius: from_world_radius(radius),
+            ..Default::default()
+        }
+    }
+
     /// Returns `true` if the node is a waypoint.
     #[inline]
     pub fn is_waypoint(&self) -> bool {
@@ -266,10 +839,7 @@ impl Node {
     /// coordinates.
     #[inline]
     pub fn world_position(&self) -> Vec2 {
-        Vec2::new(
-            self.position.x as f32 / SCALE,
-            self.position.y as f32 / SCALE,
-        )
+        to_world_position(self.position)
     }
 
     /// Returns the radius of the node in world space.
@@ -328,10 +898,411 @@ bitflags! {
     }
 }
 
+crate::flags::impl_unknown_bits_set!(NodeFlags);
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_serialized_field_names() {
+        let mut battle_tabletop_fields = vec![
+            "width",
+            "height",
+            "player_army",
+            "enemy_army",
+            "ctl",
+            "unknown1",
+            "unknown2",
+            "unknown3",
+            "objectives",
+            "obstacles_unknown1",
+            "obstacles",
+            "regions",
+            "nodes",
+        ];
+        battle_tabletop_fields.sort();
+        assert_eq!(
+            crate::serialized_field_names(&BattleTabletop::default()),
+            battle_tabletop_fields
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_format() {
+        let bytes = 0xd0edafbeu32.to_le_bytes();
+
+        assert!(matches!(
+            BattleTabletop::from_slice(&[bytes, [0; 4]].concat()),
+            Err(DecodeError::InvalidObjectHeaderId(0xd0edafbe))
+        ));
+    }
+
+    #[test]
+    fn test_from_world() {
+        let obstacle =
+            Obstacle::from_world(Vec2::new(138.625, 47.5), 7.875, ObstacleFlags::IS_ENABLED);
+
+        assert_eq!(obstacle.position, IVec2::new(1109, 380));
+        assert_eq!(obstacle.radius, 63);
+        assert_eq!(obstacle.flags, ObstacleFlags::IS_ENABLED);
+        assert_eq!(obstacle.world_position(), Vec2::new(138.625, 47.5));
+        assert_eq!(obstacle.world_radius(), 7.875);
+
+        // Positions between battle tabletop units are rounded.
+        let node = Node::from_world(Vec2::new(10.06, -0.06), 6., NodeFlags::IS_WAYPOINT);
+
+        assert_eq!(node.position, IVec2::new(80, 0));
+        assert_eq!(node.radius, 48);
+        assert!(node.is_waypoint());
+        assert_eq!(node.world_position(), Vec2::new(10., 0.));
+        assert_eq!(node.world_radius(), 6.);
+    }
+
+    #[test]
+    fn test_critical_regiment_id() {
+        let mut b = BattleTabletop::default();
+
+        assert_eq!(b.critical_regiment_id(), None);
+
+        b.objectives.push(Objective {
+            typ: Objective::CRITICAL_REGIMENT_LOSE_CONDITION_ID,
+            val1: 1,
+            val2: 0,
+        });
+
+        assert_eq!(b.critical_regiment_id(), Some(1));
+    }
+
+    #[test]
+    fn test_initial_orientation_radians() {
+        let mut b = BattleTabletop::default();
+
+        assert_eq!(b.initial_player_orientation_radians(), None);
+        assert_eq!(b.initial_enemy_orientation_radians(), None);
+
+        b.objectives.push(Objective {
+            typ: Objective::INITIAL_ORIENTATION_ID,
+            val1: 128,
+            val2: 384,
+        });
+
+        const EPSILON: f32 = 0.0001;
+
+        assert!(
+            (b.initial_player_orientation_radians().unwrap() - std::f32::consts::FRAC_PI_2).abs()
+                < EPSILON
+        );
+        assert!(
+            (b.initial_enemy_orientation_radians().unwrap() - 3. * std::f32::consts::FRAC_PI_2)
+                .abs()
+                < EPSILON
+        );
+    }
+
+    #[test]
+    fn test_validate_against_project() {
+        let mut p = Project::default();
+        p.attributes.width = 100;
+        p.attributes.height = 50;
+
+        let mut b = BattleTabletop {
+            width: 800,
+            height: 400,
+            ..Default::default()
+        };
+        b.obstacles.push(Obstacle {
+            position: IVec2::new(80, 80),
+            ..Default::default()
+        });
+
+        assert_eq!(b.validate_against_project(&p), Ok(()));
+
+        b.height = 408;
+        b.obstacles[0].position = IVec2::new(-8, 80);
+
+        assert_eq!(
+            b.validate_against_project(&p),
+            Err(vec![
+                MismatchError::HeightExceedsProject {
+                    height: 51,
+                    project_height: 50,
+                },
+                MismatchError::ObstacleOutOfBounds {
+                    index: 0,
+                    position: IVec2::new(-8, 80),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_overlay_on_terrain() {
+        use crate::project::TerrainBlock;
+
+        let mut p = Project::default();
+        p.terrain.width = 8;
+        p.terrain.height = 8;
+        p.terrain.heightmap1_blocks = vec![TerrainBlock {
+            base_height: 0,
+            height_offsets_index: 0,
+        }];
+        p.terrain.height_offsets = vec![vec![0; 64]];
+
+        let b = BattleTabletop {
+            width: 32,
+            height: 16,
+            ..Default::default()
+        };
+
+        let img = b.overlay_on_terrain(&p).to_rgba8();
+
+        let red = Rgba([255, 0, 0, 255]);
+        assert_eq!(img.dimensions(), (8, 8));
+        // The rectangle is pinned to the bottom left after rotating.
+        assert_eq!(img.get_pixel(0, 7), &red);
+        assert_eq!(img.get_pixel(3, 6), &red);
+        assert_eq!(img.get_pixel(4, 7), &Rgba([255, 255, 255, 255]));
+        assert_eq!(img.get_pixel(0, 5), &Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_deployment_zones() {
+        let region = |name: &str, flags| Region {
+            name: name.to_string(),
+            flags,
+            ..Default::default()
+        };
+
+        let b = BattleTabletop {
+            regions: vec![
+                region("a", RegionFlags::IS_PLAYER1_DEPLOYMENT_ZONE),
+                region("b", RegionFlags::NONE),
+                region("c", RegionFlags::IS_PLAYER2_DEPLOYMENT_ZONE),
+                region("d", RegionFlags::IS_PLAYER1_DEPLOYMENT_ZONE),
+            ],
+            ..Default::default()
+        };
+
+        let names =
+            |regions: Vec<&Region>| regions.iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+
+        assert_eq!(names(b.deployment_zones()), vec!["a", "c", "d"]);
+        assert_eq!(names(b.player1_deployment_zones()), vec!["a", "d"]);
+        assert_eq!(names(b.player2_deployment_zones()), vec!["c"]);
+    }
+
+    #[test]
+    fn test_line_segment_intersects() {
+        let line = |x1, y1, x2, y2| LineSegment {
+            start: IVec2::new(x1, y1),
+            end: IVec2::new(x2, y2),
+        };
+
+        assert_eq!(
+            line(0, 0, 10, 10).intersects(&line(0, 10, 10, 0)),
+            Some(IVec2::new(5, 5))
+        );
+        assert_eq!(
+            line(0, 0, 10, 0).intersects(&line(10, 0, 10, 10)),
+            Some(IVec2::new(10, 0))
+        );
+        assert_eq!(line(0, 0, 10, 0).intersects(&line(0, 5, 10, 5)), None);
+        assert_eq!(line(0, 0, 4, 4).intersects(&line(0, 10, 10, 0)), None);
+        // Collinear and overlapping.
+        assert_eq!(
+            line(0, 0, 10, 0).intersects(&line(5, 0, 15, 0)),
+            Some(IVec2::new(5, 0))
+        );
+        // Collinear but not overlapping.
+        assert_eq!(line(0, 0, 10, 0).intersects(&line(11, 0, 15, 0)), None);
+    }
+
+    #[test]
+    fn test_line_segment_distance_to_point() {
+        let line = LineSegment {
+            start: IVec2::new(0, 0),
+            end: IVec2::new(10, 0),
+        };
+
+        assert_eq!(line.distance_to_point(IVec2::new(5, 3)), 3.);
+        assert_eq!(line.distance_to_point(IVec2::new(5, 0)), 0.);
+        assert_eq!(line.distance_to_point(IVec2::new(13, 4)), 5.);
+    }
+
+    #[test]
+    fn test_region_nearest_point_inside() {
+        let region = Region {
+            line_segments: vec![
+                LineSegment {
+                    start: IVec2::new(0, 0),
+                    end: IVec2::new(10, 0),
+                },
+                LineSegment {
+                    start: IVec2::new(10, 0),
+                    end: IVec2::new(10, 10),
+                },
+                LineSegment {
+                    start: IVec2::new(10, 10),
+                    end: IVec2::new(0, 10),
+                },
+                LineSegment {
+                    start: IVec2::new(0, 10),
+                    end: IVec2::new(0, 0),
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            region.nearest_point_inside(IVec2::new(5, 5)),
+            IVec2::new(5, 5)
+        );
+        assert_eq!(
+            region.nearest_point_inside(IVec2::new(15, 5)),
+            IVec2::new(10, 5)
+        );
+        assert_eq!(
+            region.nearest_point_inside(IVec2::new(3, -4)),
+            IVec2::new(3, 0)
+        );
+        assert_eq!(
+            region.nearest_point_inside(IVec2::new(-5, 20)),
+            IVec2::new(0, 10)
+        );
+
+        assert_eq!(
+            Region::default().nearest_point_inside(IVec2::new(1, 2)),
+            IVec2::new(1, 2)
+        );
+    }
+
+    #[test]
+    fn test_world_position_conversions() {
+        assert_eq!(to_world_position(IVec2::new(8, -12)), Vec2::new(1., -1.5));
+        assert_eq!(from_world_position(Vec2::new(1., -1.5)), IVec2::new(8, -12));
+        assert_eq!(from_world_position(Vec2::new(0.06, 0.07)), IVec2::new(0, 1));
+    }
+
+    #[test]
+    fn test_join_regiments_and_nodes() {
+        let army = Army {
+            regiments: vec![
+                Regiment {
+                    id: 1,
+                    ..Default::default()
+                },
+                Regiment {
+                    id: 2,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let node = |node_id, regiment_id| Node {
+            node_id,
+            regiment_id,
+            ..Default::default()
+        };
+        let b = BattleTabletop {
+            nodes: vec![node(10, 1), node(11, 3), node(12, 1)],
+            ..Default::default()
+        };
+
+        let joined = join_regiments_and_nodes(&army, &b);
+
+        assert_eq!(joined.len(), 2);
+        assert_eq!(joined[0].0.id, 1);
+        assert_eq!(
+            joined[0].1.iter().map(|n| n.node_id).collect::<Vec<_>>(),
+            vec![10, 12]
+        );
+        assert_eq!(joined[1].0.id, 2);
+        assert!(joined[1].1.is_empty());
+    }
+
+    #[test]
+    fn test_world_bounds() {
+        let b = BattleTabletop {
+            width: 2560,
+            height: 1920,
+            ..Default::default()
+        };
+
+        assert_eq!(b.world_size(), Vec2::new(320., 240.));
+        assert_eq!(b.world_bounds(), (Vec2::ZERO, Vec2::new(320., 240.)));
+    }
+
+    #[test]
+    fn test_region_from_points() {
+        let points = [
+            IVec2::new(0, 0),
+            IVec2::new(10, 0),
+            IVec2::new(10, 10),
+            IVec2::new(0, 10),
+        ];
+
+        let region = Region::from_points(&points, RegionFlags::IS_CLOSED).unwrap();
+
+        assert_eq!(region.flags, RegionFlags::IS_CLOSED);
+        assert_eq!(region.line_segments.len(), 4);
+        assert_eq!(region.line_segments[0].start, IVec2::new(0, 0));
+        assert_eq!(region.line_segments[0].end, IVec2::new(10, 0));
+        // The last point is connected back to the first.
+        assert_eq!(region.line_segments[3].start, IVec2::new(0, 10));
+        assert_eq!(region.line_segments[3].end, IVec2::new(0, 0));
+        assert!(region.is_point_contained(IVec2::new(5, 5)));
+        assert!(!region.is_point_contained(IVec2::new(11, 5)));
+
+        assert_eq!(
+            Region::from_points(&points[..2], RegionFlags::IS_CLOSED).unwrap_err(),
+            TooFewPointsError { count: 2 }
+        );
+    }
+
+    #[test]
+    fn test_navmesh_polygons() {
+        let square = |min: i32, max: i32| {
+            [
+                IVec2::new(min, min),
+                IVec2::new(max, min),
+                IVec2::new(max, max),
+                IVec2::new(min, max),
+            ]
+        };
+
+        let b = BattleTabletop {
+            regions: vec![
+                Region::from_points(&square(0, 80), RegionFlags::IS_BATTLE_BOUNDARY).unwrap(),
+                Region::from_points(&square(16, 32), RegionFlags::IS_BOUNDARY_REVERSED).unwrap(),
+                Region::from_points(&square(40, 48), RegionFlags::IS_PLAYER1_DEPLOYMENT_ZONE)
+                    .unwrap(),
+            ],
+            ..Default::default()
+        };
+
+        let (outer, holes) = b.navmesh_polygons();
+
+        assert_eq!(
+            outer,
+            vec![vec![
+                Vec2::new(0., 0.),
+                Vec2::new(10., 0.),
+                Vec2::new(10., 10.),
+                Vec2::new(0., 10.),
+            ]]
+        );
+        assert_eq!(
+            holes,
+            vec![vec![
+                Vec2::new(2., 2.),
+                Vec2::new(4., 2.),
+                Vec2::new(4., 4.),
+                Vec2::new(2., 4.),
+            ]]
+        );
+    }
+
     #[test]
     fn test_region_is_point_contained() {
         let region = Region {
@@ -0,0 +1,159 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+/// A cache of decoded values of a single format, keyed by a hash of the bytes
+/// they were decoded from.
+///
+/// This is useful for tools that repeatedly decode the same files, e.g. an
+/// editor that reloads a game directory whenever something changes. Unchanged
+/// files are only decoded once and every later decode returns the same
+/// [`Arc`].
+///
+/// The cache keeps a copy of the bytes of every value so a hit is only
+/// returned if the bytes match, not just their hash. Nothing is evicted
+/// automatically, so every version of a file that has been decoded stays in
+/// memory. Use [`DecodeCache::remove`] to drop a version that is no longer
+/// needed, e.g. the old bytes of a file that was edited, or
+/// [`DecodeCache::clear`] to drop everything.
+#[derive(Debug)]
+pub struct DecodeCache<T> {
+    entries: Mutex<HashMap<u64, Vec<Entry<T>>>>,
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    bytes: Box<[u8]>,
+    value: Arc<T>,
+}
+
+impl<T> Default for DecodeCache<T> {
+    fn default() -> Self {
+        DecodeCache {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T> DecodeCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached value for `bytes`, or decodes it with `decode` and
+    /// caches it if there is none.
+    ///
+    /// Errors are not cached, so decoding the same bytes again retries.
+    pub fn get_or_decode<E>(
+        &self,
+        bytes: &[u8],
+        decode: impl FnOnce(&[u8]) -> Result<T, E>,
+    ) -> Result<Arc<T>, E> {
+        let key = hash_bytes(bytes);
+
+        if let Some(value) = self.get(key, bytes) {
+            return Ok(value);
+        }
+
+        // The lock is not held while decoding so that other formats or files
+        // are not blocked. If the same bytes are decoded concurrently, the
+        // first value to be inserted wins.
+        let value = Arc::new(decode(bytes)?);
+
+        let mut entries = self.entries.lock().unwrap();
+        let bucket = entries.entry(key).or_default();
+        if let Some(entry) = bucket.iter().find(|entry| *entry.bytes == *bytes) {
+            return Ok(Arc::clone(&entry.value));
+        }
+        bucket.push(Entry {
+            bytes: bytes.into(),
+            value: Arc::clone(&value),
+        });
+
+        Ok(value)
+    }
+
+    fn get(&self, key: u64, bytes: &[u8]) -> Option<Arc<T>> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&key)?
+            .iter()
+            .find(|entry| *entry.bytes == *bytes)
+            .map(|entry| Arc::clone(&entry.value))
+    }
+
+    /// Removes the cached value for `bytes` and returns it, if there is one.
+    pub fn remove(&self, bytes: &[u8]) -> Option<Arc<T>> {
+        let key = hash_bytes(bytes);
+
+        let mut entries = self.entries.lock().unwrap();
+        let bucket = entries.get_mut(&key)?;
+        let index = bucket.iter().position(|entry| *entry.bytes == *bytes)?;
+        let entry = bucket.swap_remove(index);
+        if bucket.is_empty() {
+            entries.remove(&key);
+        }
+
+        Some(entry.value)
+    }
+
+    /// Returns the number of cached values.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().values().map(Vec::len).sum()
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached values.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_or_decode() {
+        let cache = DecodeCache::new();
+        let mut decode_count = 0;
+        let mut decode = |bytes: &[u8]| -> Result<usize, ()> {
+            decode_count += 1;
+            Ok(bytes.len())
+        };
+
+        let a = cache.get_or_decode(b"abc", &mut decode).unwrap();
+        let b = cache.get_or_decode(b"abc", &mut decode).unwrap();
+        let c = cache.get_or_decode(b"abcd", &mut decode).unwrap();
+
+        assert_eq!(*a, 3);
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(*c, 4);
+        assert_eq!(decode_count, 2);
+        assert_eq!(cache.len(), 2);
+
+        // Errors are not cached.
+        assert!(cache.get_or_decode(b"", |_| Err::<usize, _>(())).is_err());
+        assert_eq!(cache.len(), 2);
+
+        assert!(Arc::ptr_eq(&cache.remove(b"abc").unwrap(), &a));
+        assert!(cache.remove(b"abc").is_none());
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
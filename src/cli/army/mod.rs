@@ -32,6 +32,11 @@ pub struct EditArmyArgs {
     #[arg(short, long, default_value_t=Format::Json)]
     #[clap(value_enum)]
     pub format: Format,
+
+    /// Serialize the army file without any whitespace. By default, it is
+    /// serialized with indentation and new lines.
+    #[arg(long)]
+    pub compact: bool,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -56,12 +61,14 @@ fn edit_army_file(args: &EditArmyArgs) -> anyhow::Result<()> {
     let army = Decoder::new(file).decode()?;
 
     // Serialize the army to a human-readable string.
-    let (as_string, extension) = match args.format {
-        Format::Ron => (
-            ron::ser::to_string_pretty(&army, ron::ser::PrettyConfig::default())?,
+    let (as_string, extension) = match (&args.format, args.compact) {
+        (Format::Ron, false) => (
+            ron::ser::to_string_pretty(&army, darkomen::ron_config())?,
             "ron",
         ),
-        Format::Json => (serde_json::to_string_pretty(&army)?, "json"),
+        (Format::Ron, true) => (ron::ser::to_string(&army)?, "ron"),
+        (Format::Json, false) => (serde_json::to_string_pretty(&army)?, "json"),
+        (Format::Json, true) => (serde_json::to_string(&army)?, "json"),
     };
 
     // Write the human-readable string to a temporary file.
@@ -99,6 +106,12 @@ fn edit_army_file(args: &EditArmyArgs) -> anyhow::Result<()> {
     let mut modified_string = String::new();
     temp_file.reopen()?.read_to_string(&mut modified_string)?;
 
+    // Leave the original file untouched if nothing was changed.
+    if modified_string == as_string {
+        println!("Army file unchanged");
+        return Ok(());
+    }
+
     // Deserialize the modified string to an army.
     let modified_army = match args.format {
         Format::Ron => ron::de::from_str(&modified_string)?,
@@ -1,2 +1,3 @@
 pub mod army;
 pub mod project;
+pub mod sprite_sheet;
@@ -1,11 +1,11 @@
 use std::{
     fs::File,
     io::{Read as _, Write as _},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use clap::{Args, Subcommand, ValueEnum};
-use darkomen::project::*;
+use darkomen::{battle_tabletop::BattleTabletop, project::*, shadow};
 
 #[derive(Debug, Args)]
 pub struct ProjectArgs {
@@ -16,6 +16,7 @@ pub struct ProjectArgs {
 #[derive(Debug, Subcommand)]
 pub enum ProjectSubcommands {
     Edit(EditProjectArgs),
+    Render(RenderProjectArgs),
 }
 
 #[derive(Debug, Args)]
@@ -32,6 +33,23 @@ pub struct EditProjectArgs {
     #[arg(short, long, default_value_t=Format::Json)]
     #[clap(value_enum)]
     pub format: Format,
+
+    /// Serialize the project file without any whitespace. By default, it is
+    /// serialized with indentation and new lines.
+    #[arg(long)]
+    pub compact: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RenderProjectArgs {
+    /// The path to the project file to render, e.g. ".../B1_01/B1_01.PRJ".
+    #[arg(index = 1)]
+    pub project_file: String,
+
+    /// The directory to write the images to. Defaults to the current
+    /// directory.
+    #[arg(short, long, default_value = ".")]
+    pub output_dir: String,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -41,8 +59,10 @@ pub enum Format {
 }
 
 pub fn run(args: &ProjectArgs) -> anyhow::Result<()> {
-    if let Some(ProjectSubcommands::Edit(edit_args)) = &args.subcommand {
-        edit_project_file(edit_args)?;
+    match &args.subcommand {
+        Some(ProjectSubcommands::Edit(edit_args)) => edit_project_file(edit_args)?,
+        Some(ProjectSubcommands::Render(render_args)) => render_project_file(render_args)?,
+        None => {}
     }
 
     Ok(())
@@ -56,12 +76,14 @@ fn edit_project_file(args: &EditProjectArgs) -> anyhow::Result<()> {
     let project = Decoder::new(file).decode()?;
 
     // Serialize the project to a human-readable string.
-    let (as_string, extension) = match args.format {
-        Format::Ron => (
-            ron::ser::to_string_pretty(&project, ron::ser::PrettyConfig::default())?,
+    let (as_string, extension) = match (&args.format, args.compact) {
+        (Format::Ron, false) => (
+            ron::ser::to_string_pretty(&project, darkomen::ron_config())?,
             "ron",
         ),
-        Format::Json => (serde_json::to_string_pretty(&project)?, "json"),
+        (Format::Ron, true) => (ron::ser::to_string(&project)?, "ron"),
+        (Format::Json, false) => (serde_json::to_string_pretty(&project)?, "json"),
+        (Format::Json, true) => (serde_json::to_string(&project)?, "json"),
     };
 
     // Write the human-readable string to a temporary file.
@@ -99,7 +121,13 @@ fn edit_project_file(args: &EditProjectArgs) -> anyhow::Result<()> {
     let mut modified_string = String::new();
     temp_file.reopen()?.read_to_string(&mut modified_string)?;
 
-    // Deserialize the modified string to an project.
+    // Leave the original file untouched if nothing was changed.
+    if modified_string == as_string {
+        println!("Project file unchanged");
+        return Ok(());
+    }
+
+    // Deserialize the modified string to a project.
     let modified_project = match args.format {
         Format::Ron => ron::de::from_str(&modified_string)?,
         Format::Json => serde_json::from_str(&modified_string)?,
@@ -113,3 +141,69 @@ fn edit_project_file(args: &EditProjectArgs) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn render_project_file(args: &RenderProjectArgs) -> anyhow::Result<()> {
+    let project_file: PathBuf = args.project_file.clone().into();
+    let output_dir: PathBuf = args.output_dir.clone().into();
+
+    // Load the project file.
+    let file = File::open(project_file.clone())?;
+    let project = Decoder::new(file).decode()?;
+
+    std::fs::create_dir_all(&output_dir)?;
+
+    let stem = project_file
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("project");
+    let output_path = |kind: &str| output_dir.join(format!("{}.{}.png", stem, kind));
+
+    // Write the furniture and base heightmaps.
+    project
+        .terrain
+        .furniture_heightmap_image()
+        .save(output_path("furniture_heightmap"))?;
+    project
+        .terrain
+        .base_heightmap_image()
+        .save(output_path("base_heightmap"))?;
+
+    let dir = project_file
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    // Write the lightmap and the battle tabletop overlay if the project has
+    // them.
+    if let Some(shd_file) = find_sibling_file(dir, &format!("{}.SHD", stem))? {
+        let lightmap = shadow::Decoder::new(File::open(shd_file)?).decode()?;
+        lightmap.image().save(output_path("lightmap"))?;
+    }
+    if let Some(btb_file) = find_sibling_file(dir, &format!("{}.BTB", stem))? {
+        let battle_tabletop = BattleTabletop::from_slice(&std::fs::read(btb_file)?)?;
+        battle_tabletop
+            .overlay_on_terrain(&project)
+            .save(output_path("overlay"))?;
+    }
+
+    println!("Rendered project images to {}", output_dir.display());
+
+    Ok(())
+}
+
+/// Returns the path of the file named `file_name` in `dir`, ignoring case, or
+/// `None` if there is no such file.
+fn find_sibling_file(dir: &Path, file_name: &str) -> anyhow::Result<Option<PathBuf>> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry
+            .file_name()
+            .to_string_lossy()
+            .eq_ignore_ascii_case(file_name)
+        {
+            return Ok(Some(entry.path()));
+        }
+    }
+
+    Ok(None)
+}
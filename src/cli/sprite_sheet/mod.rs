@@ -0,0 +1,65 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::{Args, Subcommand};
+use darkomen::graphics::sprite_sheet::*;
+
+#[derive(Debug, Args)]
+pub struct SpriteSheetArgs {
+    #[command(subcommand)]
+    pub subcommand: Option<SpriteSheetSubcommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SpriteSheetSubcommands {
+    Extract(ExtractSpriteSheetArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ExtractSpriteSheetArgs {
+    /// The path to the sprite sheet file to extract, e.g.
+    /// ".../BANNERS/EMP01.SPR".
+    #[arg(index = 1)]
+    pub sprite_sheet_file: String,
+
+    /// The directory to write the frames to. Defaults to the current
+    /// directory.
+    #[arg(short, long, default_value = ".")]
+    pub output_dir: String,
+}
+
+pub fn run(args: &SpriteSheetArgs) -> anyhow::Result<()> {
+    if let Some(SpriteSheetSubcommands::Extract(extract_args)) = &args.subcommand {
+        extract_sprite_sheet_file(extract_args)?;
+    }
+
+    Ok(())
+}
+
+fn extract_sprite_sheet_file(args: &ExtractSpriteSheetArgs) -> anyhow::Result<()> {
+    let sprite_sheet_file: PathBuf = args.sprite_sheet_file.clone().into();
+    let output_dir: PathBuf = args.output_dir.clone().into();
+
+    // Load the sprite sheet file.
+    let file = File::open(sprite_sheet_file.clone())?;
+    let sprite_sheet = Decoder::new(file).decode()?;
+
+    std::fs::create_dir_all(&output_dir)?;
+
+    let stem = sprite_sheet_file
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("sprite");
+
+    // Write each frame as a PNG.
+    for (i, texture) in sprite_sheet.textures.iter().enumerate() {
+        texture.save(output_dir.join(format!("{}_{}.png", stem, i)))?;
+    }
+
+    println!(
+        "Extracted {} frames to {}",
+        sprite_sheet.textures.len(),
+        output_dir.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use bitflags::Flags;
+
+/// Returns the bits of `flags` that are set and whose named constant starts
+/// with `UNKNOWN`, i.e. the bits whose meaning is yet to be reverse engineered.
+pub(crate) fn unknown_bits_set<T: Flags>(flags: &T) -> T {
+    let unknown = T::FLAGS
+        .iter()
+        .filter(|flag| flag.name().starts_with("UNKNOWN"))
+        .fold(T::empty(), |acc, flag| {
+            acc.union(T::from_bits_retain(flag.value().bits()))
+        });
+
+    T::from_bits_retain(flags.bits()).intersection(unknown)
+}
+
+/// Implements `unknown_bits_set` for a bitflags type.
+macro_rules! impl_unknown_bits_set {
+    ($ty:ty) => {
+        impl $ty {
+            /// Returns the set bits whose meaning is unknown, i.e. those with
+            /// an `UNKNOWN_*` constant.
+            pub fn unknown_bits_set(&self) -> Self {
+                $crate::flags::unknown_bits_set(self)
+            }
+        }
+    };
+}
+
+pub(crate) use impl_unknown_bits_set;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle_tabletop::ObstacleFlags;
+
+    #[test]
+    fn test_unknown_bits_set() {
+        let flags = ObstacleFlags::IS_ENABLED
+            | ObstacleFlags::BLOCKS_MOVEMENT
+            | ObstacleFlags::UNKNOWN_FLAG_1
+            | ObstacleFlags::UNKNOWN_FLAG_13;
+
+        assert_eq!(
+            unknown_bits_set(&flags),
+            ObstacleFlags::UNKNOWN_FLAG_1 | ObstacleFlags::UNKNOWN_FLAG_13
+        );
+        assert_eq!(
+            unknown_bits_set(&ObstacleFlags::IS_ENABLED),
+            ObstacleFlags::empty()
+        );
+    }
+}
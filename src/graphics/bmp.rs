@@ -0,0 +1,243 @@
+use image::{DynamicImage, GenericImage, Rgba};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    InvalidFormat(String),
+    InvalidHeaderSize(u32),
+    UnsupportedBitDepth(u16),
+    UnsupportedCompression(u32),
+    InvalidPaletteSize(u32),
+    UnexpectedEof,
+}
+
+impl std::error::Error for DecodeError {}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidFormat(format) => write!(f, "invalid format: {}", format),
+            DecodeError::InvalidHeaderSize(size) => write!(f, "invalid header size: {}", size),
+            DecodeError::UnsupportedBitDepth(bit_depth) => {
+                write!(f, "unsupported bit depth: {}", bit_depth)
+            }
+            DecodeError::UnsupportedCompression(compression) => {
+                write!(f, "unsupported compression: {}", compression)
+            }
+            DecodeError::InvalidPaletteSize(size) => write!(f, "invalid palette size: {}", size),
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of file"),
+        }
+    }
+}
+
+/// The BMP format ID.
+const FORMAT: &str = "BM";
+
+const FILE_HEADER_SIZE_BYTES: usize = 14;
+/// The size of the `BITMAPINFOHEADER`. Later versions of the header are larger
+/// but start with the same fields.
+const MIN_INFO_HEADER_SIZE_BYTES: u32 = 40;
+
+/// Loads an 8-bit paletted BMP, which is the format of Dark Omen's textures.
+///
+/// The palette is expanded to RGBA. If `color_keyed` is `true`, pixels that use
+/// palette index 0 are made fully transparent, regardless of the color stored
+/// in the palette at that index.
+///
+/// Returns [`DecodeError::InvalidFormat`] if the bytes are not a BMP,
+/// [`DecodeError::UnsupportedBitDepth`] if the BMP is not paletted and
+/// [`DecodeError::UnsupportedCompression`] if it is compressed. In these cases a
+/// generic image loader should be used instead.
+pub fn load_paletted_bmp(bytes: &[u8], color_keyed: bool) -> Result<DynamicImage, DecodeError> {
+    if !bytes.starts_with(FORMAT.as_bytes()) {
+        let format = String::from_utf8_lossy(&bytes[..bytes.len().min(2)]).to_string();
+        return Err(DecodeError::InvalidFormat(format));
+    }
+
+    if bytes.len() < FILE_HEADER_SIZE_BYTES + MIN_INFO_HEADER_SIZE_BYTES as usize {
+        return Err(DecodeError::UnexpectedEof);
+    }
+
+    let pixel_data_offset = u32::from_le_bytes(bytes[10..14].try_into().unwrap()) as usize;
+
+    let info = &bytes[FILE_HEADER_SIZE_BYTES..];
+    let info_header_size = u32::from_le_bytes(info[0..4].try_into().unwrap());
+    if info_header_size < MIN_INFO_HEADER_SIZE_BYTES {
+        return Err(DecodeError::InvalidHeaderSize(info_header_size));
+    }
+
+    let width = i32::from_le_bytes(info[4..8].try_into().unwrap()).unsigned_abs();
+    let height = i32::from_le_bytes(info[8..12].try_into().unwrap());
+    // A negative height means the rows are stored top to bottom instead of the
+    // usual bottom to top.
+    let top_down = height < 0;
+    let height = height.unsigned_abs();
+
+    let bit_depth = u16::from_le_bytes(info[14..16].try_into().unwrap());
+    if bit_depth != 8 {
+        return Err(DecodeError::UnsupportedBitDepth(bit_depth));
+    }
+
+    let compression = u32::from_le_bytes(info[16..20].try_into().unwrap());
+    if compression != 0 {
+        return Err(DecodeError::UnsupportedCompression(compression));
+    }
+
+    // A value of 0 means the palette has the maximum number of colors for the
+    // bit depth.
+    let palette_size = match u32::from_le_bytes(info[32..36].try_into().unwrap()) {
+        0 => 256,
+        n if n <= 256 => n,
+        n => return Err(DecodeError::InvalidPaletteSize(n)),
+    };
+
+    let palette_offset = FILE_HEADER_SIZE_BYTES + info_header_size as usize;
+    let palette = bytes
+        .get(palette_offset..palette_offset + palette_size as usize * 4)
+        .ok_or(DecodeError::UnexpectedEof)?
+        .chunks_exact(4)
+        // Palette entries are stored as BGR with a reserved byte.
+        .map(|c| Rgba([c[2], c[1], c[0], 255]))
+        .collect::<Vec<_>>();
+
+    // Each row is padded to a multiple of 4 bytes.
+    let row_size = (width as usize).div_ceil(4) * 4;
+
+    // Check the pixel data is all there before allocating the image, so a
+    // corrupt header can't ask for a huge allocation.
+    let pixel_data_end = (height as usize)
+        .checked_mul(row_size)
+        .and_then(|size| size.checked_add(pixel_data_offset));
+    if pixel_data_end.is_none_or(|end| end > bytes.len()) {
+        return Err(DecodeError::UnexpectedEof);
+    }
+
+    let mut img = DynamicImage::new_rgba8(width, height);
+
+    for row in 0..height {
+        let row_offset = pixel_data_offset + row as usize * row_size;
+        let indices = bytes
+            .get(row_offset..row_offset + width as usize)
+            .ok_or(DecodeError::UnexpectedEof)?;
+
+        let y = if top_down { row } else { height - 1 - row };
+
+        for (x, &index) in indices.iter().enumerate() {
+            let color = if color_keyed && index == 0 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                // Indices outside the palette are treated as black, which is
+                // what the game does.
+                palette
+                    .get(index as usize)
+                    .copied()
+                    .unwrap_or(Rgba([0, 0, 0, 255]))
+            };
+
+            img.put_pixel(x as u32, y, color);
+        }
+    }
+
+    Ok(img)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GenericImageView as _;
+
+    use super::*;
+
+    /// Builds a 3x2 8-bit paletted BMP with a 3 color palette.
+    fn test_bmp(top_down: bool) -> Vec<u8> {
+        let width: i32 = 3;
+        let height: i32 = if top_down { -2 } else { 2 };
+        let palette: [[u8; 4]; 3] = [[0, 0, 0, 0], [0, 0, 255, 0], [255, 0, 0, 0]]; // BGR0
+        let rows: [[u8; 4]; 2] = [[0, 1, 2, 0], [2, 1, 0, 0]]; // padded to 4 bytes
+
+        let pixel_data_offset = 14 + 40 + palette.len() * 4;
+        let file_size = pixel_data_offset + rows.len() * 4;
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"BM");
+        bytes.extend_from_slice(&(file_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&(pixel_data_offset as u32).to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&8u16.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&(palette.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        for entry in palette {
+            bytes.extend_from_slice(&entry);
+        }
+        for row in rows {
+            bytes.extend_from_slice(&row);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_load_paletted_bmp() {
+        let img = load_paletted_bmp(&test_bmp(false), false).unwrap();
+
+        assert_eq!(img.dimensions(), (3, 2));
+        // Rows are stored bottom to top.
+        assert_eq!(img.get_pixel(0, 1), Rgba([0, 0, 0, 255]));
+        assert_eq!(img.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+        assert_eq!(img.get_pixel(2, 1), Rgba([0, 0, 255, 255]));
+        assert_eq!(img.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+        assert_eq!(img.get_pixel(2, 0), Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_load_paletted_bmp_top_down() {
+        let img = load_paletted_bmp(&test_bmp(true), false).unwrap();
+
+        assert_eq!(img.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+        assert_eq!(img.get_pixel(2, 0), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_load_paletted_bmp_color_keyed() {
+        let img = load_paletted_bmp(&test_bmp(false), true).unwrap();
+
+        assert_eq!(img.get_pixel(0, 1), Rgba([0, 0, 0, 0]));
+        assert_eq!(img.get_pixel(2, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(img.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_load_paletted_bmp_unsupported_bit_depth() {
+        let mut bytes = test_bmp(false);
+        bytes[28..30].copy_from_slice(&24u16.to_le_bytes());
+
+        assert!(matches!(
+            load_paletted_bmp(&bytes, false),
+            Err(DecodeError::UnsupportedBitDepth(24))
+        ));
+    }
+
+    #[test]
+    fn test_load_paletted_bmp_truncated() {
+        let mut bytes = test_bmp(false);
+        bytes[22..26].copy_from_slice(&i32::MAX.to_le_bytes());
+
+        assert!(matches!(
+            load_paletted_bmp(&bytes, false),
+            Err(DecodeError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn test_load_paletted_bmp_invalid_format() {
+        assert!(matches!(
+            load_paletted_bmp(b"\x89PNG", false),
+            Err(DecodeError::InvalidFormat(_))
+        ));
+    }
+}
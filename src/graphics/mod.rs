@@ -1,3 +1,15 @@
+//! Graphics formats.
+//!
+//! Sprite sheets (`.SPR`), e.g. banners and unit sprites, are decoded with
+//! [`sprite_sheet::Decoder`]. Each frame of the sprite sheet is decoded into an
+//! image in [`SpriteSheet::textures`], with its offset and size in the
+//! matching [`SpriteSheet::texture_descriptors`] entry.
+//!
+//! Textures (`.BMP`) are 8-bit paletted images and can be loaded with
+//! [`load_paletted_bmp`].
+
+pub mod bmp;
 pub mod sprite_sheet;
 
+pub use bmp::load_paletted_bmp;
 pub use sprite_sheet::*;
@@ -97,6 +97,11 @@ enum SpriteType {
     Empty = 5,
 }
 
+/// Decodes a sprite sheet from a `.SPR` file.
+///
+/// The reader must implement [`Seek`] because each sprite's data is read from
+/// the offset given in its header. To decode from a stream, read it into
+/// memory first and wrap it in a [`std::io::Cursor`].
 pub struct Decoder<R>
 where
     R: Read + Seek,
@@ -289,15 +294,11 @@ mod tests {
 
     #[test]
     fn test_decode_bernhd() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GRAPHICS",
-            "SPRITES",
-            "BERNHD.SPR",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path()
+            .unwrap()
+            .join("GRAPHICS")
+            .join("SPRITES")
+            .join("BERNHD.SPR");
 
         let file = File::open(d.clone()).unwrap();
 
@@ -309,15 +310,11 @@ mod tests {
 
     #[test]
     fn test_decode_hbgrucav() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GRAPHICS",
-            "BANNERS",
-            "HBGRUCAV.SPR",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path()
+            .unwrap()
+            .join("GRAPHICS")
+            .join("BANNERS")
+            .join("HBGRUCAV.SPR");
 
         let file = File::open(d.clone()).unwrap();
 
@@ -329,15 +326,11 @@ mod tests {
 
     #[test]
     fn test_decode_mi() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GRAPHICS",
-            "SPRITES",
-            "MI.SPR",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path()
+            .unwrap()
+            .join("GRAPHICS")
+            .join("SPRITES")
+            .join("MI.SPR");
 
         let file = File::open(d.clone()).unwrap();
 
@@ -364,13 +357,7 @@ mod tests {
 
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GRAPHICS",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path().unwrap().join("GRAPHICS");
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "sprite-sheets"]
             .iter()
@@ -417,7 +404,7 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &sheet, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &sheet, crate::ron_config()).unwrap();
 
             let output_dir = output_dir.join(path.file_stem().unwrap());
             std::fs::create_dir_all(&output_dir).unwrap();
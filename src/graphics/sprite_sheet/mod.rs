@@ -6,13 +6,13 @@ mod zeroruns;
 use bevy_reflect::prelude::*;
 use glam::Vec2;
 use image::DynamicImage;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 pub use decoder::{DecodeError, Decoder};
 pub(crate) use packbits::PackBitsReader;
 pub(crate) use zeroruns::ZeroRunsReader;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct SpriteSheet {
     #[serde(skip)]
@@ -21,7 +21,7 @@ pub struct SpriteSheet {
     pub texture_descriptors: Vec<TextureDescriptor>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct TextureDescriptor {
     pub x: i16,
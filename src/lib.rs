@@ -1,10 +1,232 @@
+//! Decoders and encoders for the file formats of the game Warhammer: Dark Omen.
+//!
+//! The game was released for PC, so all multi-byte values in its binary
+//! formats are little-endian. Where a format has a magic number or format ID,
+//! its decoder checks it before anything else and returns an error straight
+//! away, rather than decoding garbage from the wrong kind of file.
+//!
+//! Each format has a `Decoder` that reads the file sequentially, so any
+//! [`std::io::Read`] works, including network streams and pipes. The only
+//! exceptions are [`battle_tabletop::Decoder`] and
+//! [`graphics::sprite_sheet::Decoder`], which also need [`std::io::Seek`].
+//!
+//! Besides its `Decoder`, the main type of most formats has a `from_slice`
+//! function, e.g. [`army::Army::from_slice`], to decode from a byte slice such
+//! as the contents of a memory-mapped file. The slice is read in place, so no
+//! intermediate copy of the file is made.
+//!
+//! The decoded types implement `Serialize` and `Deserialize` so that they can
+//! be saved as RON or JSON, e.g. with the CLI. The fields of the main types,
+//! i.e. [`army::Army`], [`army::Regiment`], [`project::Project`],
+//! [`project::Instance`] and [`battle_tabletop::BattleTabletop`], have an
+//! explicit `#[serde(rename = "...")]`, so their serialized names are stable
+//! and do not change if a field is renamed in Rust. This lets decoded files be
+//! kept in version control. Fields that only exist to help with debugging,
+//! e.g. hex dumps of unknown bytes, are not serialized at all.
+
 pub mod army;
 #[cfg(feature = "asset")]
 pub mod asset;
 pub mod battle_tabletop;
+pub mod cache;
+mod flags;
 pub mod graphics;
 pub mod light;
 pub mod m3d;
 pub mod project;
 pub mod shadow;
 pub mod sound;
+
+/// Re-export of the version of [`glam`] used for vector types in the public
+/// API, so that consumers do not need to depend on a matching version
+/// themselves.
+///
+/// Vector types convert to and from plain arrays, e.g. `[f32; 3]` for
+/// [`glam::Vec3`] and `[i32; 2]` for [`glam::IVec2`], using [`From`] and
+/// [`Into`], and they are serialized as plain arrays in the same component
+/// order.
+pub use glam;
+
+/// Returns the path to the game's `DARKOMEN` directory, based on the
+/// `DARKOMEN_PATH` environment variable, or `None` if the variable is not set.
+///
+/// `DARKOMEN_PATH` should point to the directory that contains the `DARKOMEN`
+/// directory, e.g. the root of the game CD.
+pub fn game_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("DARKOMEN_PATH").map(|path| std::path::PathBuf::from(path).join("DARKOMEN"))
+}
+
+/// Returns the path to the game's `GAMEDATA` directory, or `None` if the
+/// `DARKOMEN_PATH` environment variable is not set. See [`game_path`].
+pub fn gamedata_path() -> Option<std::path::PathBuf> {
+    game_path().map(|path| path.join("GAMEDATA"))
+}
+
+/// Returns the pretty config used whenever the crate serializes to RON.
+///
+/// The output is tuned to be stable and easy to review in version control:
+/// 2-space indentation, struct names included and tuple members on their own
+/// lines.
+#[cfg(feature = "ron")]
+pub fn ron_config() -> ron::ser::PrettyConfig {
+    ron::ser::PrettyConfig::new()
+        .indentor("  ".to_string())
+        .struct_names(true)
+        .separate_tuple_members(true)
+}
+
+/// Registers all of the crate's reflected types with the given type registry.
+///
+/// In a Bevy app, [`asset::AssetPlugin`] already does this with the app's
+/// type registry. Otherwise, call it with e.g.
+/// `app.world().resource::<AppTypeRegistry>().write()`.
+#[cfg(feature = "bevy_reflect")]
+pub fn register_reflect_types(registry: &mut bevy_reflect::TypeRegistry) {
+    registry.register::<army::Army>();
+    registry.register::<army::ArmyRace>();
+    registry.register::<army::ArmySummary>();
+    registry.register::<army::CutsceneAnimation>();
+    registry.register::<army::FileKind>();
+    registry.register::<army::LastBattleReport>();
+    registry.register::<army::LastBattleStats>();
+    registry.register::<army::MageClass>();
+    registry.register::<army::Projectile>();
+    registry.register::<army::Regiment>();
+    registry.register::<army::RegimentAlignment>();
+    registry.register::<army::RegimentAttributes>();
+    registry.register::<army::RegimentClass>();
+    registry.register::<army::RegimentFlags>();
+    registry.register::<army::RegimentMount>();
+    registry.register::<army::RegimentRace>();
+    registry.register::<army::RegimentType>();
+    registry.register::<army::SaveGameFooter>();
+    registry.register::<army::SaveGameHeader>();
+    registry.register::<army::ScriptState>();
+    registry.register::<army::SpellBook>();
+    registry.register::<army::UnitProfile>();
+    registry.register::<army::UnitStats>();
+    registry.register::<army::Weapon>();
+
+    registry.register::<battle_tabletop::BattleTabletop>();
+    registry.register::<battle_tabletop::LineSegment>();
+    registry.register::<battle_tabletop::Node>();
+    registry.register::<battle_tabletop::NodeFlags>();
+    registry.register::<battle_tabletop::Objective>();
+    registry.register::<battle_tabletop::Obstacle>();
+    registry.register::<battle_tabletop::ObstacleFlags>();
+    registry.register::<battle_tabletop::Region>();
+    registry.register::<battle_tabletop::RegionFlags>();
+
+    registry.register::<graphics::sprite_sheet::SpriteSheet>();
+    registry.register::<graphics::sprite_sheet::TextureDescriptor>();
+
+    registry.register::<light::Light>();
+    registry.register::<light::LightFlags>();
+
+    registry.register::<m3d::Face>();
+    registry.register::<m3d::M3d>();
+    registry.register::<m3d::M3dStats>();
+    registry.register::<m3d::M3dTextureDescriptor>();
+    registry.register::<m3d::Object>();
+    registry.register::<m3d::ObjectFlags>();
+    registry.register::<m3d::Vertex>();
+
+    registry.register::<project::Attributes>();
+    registry.register::<project::Excl>();
+    registry.register::<project::Instance>();
+    registry.register::<project::Project>();
+    registry.register::<project::Terrain>();
+    registry.register::<project::TerrainBlock>();
+    registry.register::<project::Track>();
+    registry.register::<project::TrackControlPoint>();
+    registry.register::<project::TrackControlPointFlags>();
+
+    registry.register::<shadow::Lightmap>();
+    registry.register::<shadow::LightmapBlock>();
+
+    registry.register::<sound::script::Pattern>();
+    registry.register::<sound::script::PatternId>();
+    registry.register::<sound::script::Script>();
+    registry.register::<sound::script::Sequence>();
+
+    registry.register::<sound::sfx::Packet>();
+    registry.register::<sound::sfx::Sfx>();
+    registry.register::<sound::sfx::SfxFlags>();
+    registry.register::<sound::sfx::SfxType>();
+    registry.register::<sound::sfx::Sound>();
+}
+
+/// Returns the names of the fields that `value` is serialized with, sorted.
+#[cfg(test)]
+pub(crate) fn serialized_field_names<T: serde::Serialize>(value: &T) -> Vec<String> {
+    let mut names: Vec<String> = serde_json::to_value(value)
+        .unwrap()
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(all(test, feature = "bevy_reflect"))]
+mod tests {
+    use std::any::TypeId;
+
+    use super::*;
+
+    #[test]
+    fn test_register_reflect_types() {
+        let mut registry = bevy_reflect::TypeRegistry::new();
+        register_reflect_types(&mut registry);
+
+        assert!(registry.contains(TypeId::of::<army::Army>()));
+        assert!(registry.contains(TypeId::of::<army::RegimentFlags>()));
+        assert!(registry.contains(TypeId::of::<project::Project>()));
+        assert!(registry.contains(TypeId::of::<battle_tabletop::BattleTabletop>()));
+    }
+
+    #[test]
+    fn test_from_reflect() {
+        use bevy_reflect::{prelude::*, ReflectFromReflect};
+
+        let mut registry = bevy_reflect::TypeRegistry::new();
+        register_reflect_types(&mut registry);
+
+        let army = army::Army {
+            name: "Grudgebringers".to_string(),
+            race: army::ArmyRace::GREENSKINS,
+            regiments: vec![army::Regiment {
+                id: 1,
+                flags: army::RegimentFlags::ACTIVE | army::RegimentFlags::TEMPORARY,
+                mage_class: army::MageClass::MasterMage,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Go through a dynamic value, like a scene deserializer would.
+        let dynamic = army.clone_value();
+        let from_reflect = registry
+            .get_type_data::<ReflectFromReflect>(TypeId::of::<army::Army>())
+            .expect("Army should have FromReflect registered");
+        let value = from_reflect.from_reflect(&*dynamic).unwrap();
+        let value = value.downcast_ref::<army::Army>().unwrap();
+
+        assert_eq!(value.name, "Grudgebringers");
+        assert_eq!(value.race, army::ArmyRace::GREENSKINS);
+        assert_eq!(value.regiments[0].id, 1);
+        // Opaque bitflags survive the round trip.
+        assert_eq!(
+            value.regiments[0].flags,
+            army::RegimentFlags::ACTIVE | army::RegimentFlags::TEMPORARY
+        );
+        assert_eq!(value.regiments[0].mage_class, army::MageClass::MasterMage);
+
+        let project = <project::Project as FromReflect>::from_reflect(
+            &*project::Project::default().clone_value(),
+        );
+        assert!(project.is_some());
+    }
+}
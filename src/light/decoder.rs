@@ -1,6 +1,6 @@
 use std::{
     fmt,
-    io::{Error as IoError, Read, Seek},
+    io::{Error as IoError, Read},
 };
 
 use glam::Vec3;
@@ -37,14 +37,15 @@ impl fmt::Display for DecodeError {
     }
 }
 
+/// Decodes lights from a `.LIT` file.
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 pub use decoder::{DecodeError, Decoder};
 pub use encoder::{EncodeError, Encoder};
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Light {
     pub position: Vec3,
@@ -88,6 +88,8 @@ bitflags! {
     }
 }
 
+crate::flags::impl_unknown_bits_set!(LightFlags);
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -133,16 +135,11 @@ mod tests {
 
     #[test]
     fn test_decode_b1_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B1_01",
-            "B1_01.LIT",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.LIT");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -154,16 +151,28 @@ mod tests {
         roundtrip_test(&original_bytes, &lights);
     }
 
+    #[test]
+    fn test_json_roundtrip_b1_01() {
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.LIT");
+
+        let original_bytes = std::fs::read(d.clone()).unwrap();
+
+        let file = File::open(d).unwrap();
+        let lights = Decoder::new(file).decode().unwrap();
+
+        let json = serde_json::to_string(&lights).unwrap();
+        let lights: Vec<Light> = serde_json::from_str(&json).unwrap();
+
+        roundtrip_test(&original_bytes, &lights);
+    }
+
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path().unwrap().join("1PBAT");
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "lights"]
             .iter()
@@ -224,7 +233,7 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &lights, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &lights, crate::ron_config()).unwrap();
         });
     }
 
@@ -1,7 +1,7 @@
 use std::{
     ffi::CStr,
     fmt,
-    io::{Error as IoError, Read, Seek},
+    io::{self, Error as IoError, Read},
 };
 
 use glam::Vec3;
@@ -44,14 +44,15 @@ impl fmt::Display for DecodeError {
     }
 }
 
+/// Decodes a model from an `.M3D` or `.M3X` file.
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
@@ -70,6 +71,19 @@ impl<R: Read + Seek> Decoder<R> {
         })
     }
 
+    /// Returns an iterator that decodes the objects one at a time, so that the
+    /// whole model does not need to be held in memory.
+    ///
+    /// The header and texture descriptors are read and discarded when the
+    /// first object is requested. If reading fails, the error is yielded and
+    /// the iterator ends.
+    pub fn objects_iter(self) -> ObjectsIter<R> {
+        ObjectsIter {
+            decoder: self,
+            remaining: None,
+        }
+    }
+
     fn decode_header(&mut self) -> Result<Header, DecodeError> {
         let mut buf = [0; HEADER_SIZE_BYTES];
         self.reader.read_exact(&mut buf)?;
@@ -131,6 +145,19 @@ impl<R: Read + Seek> Decoder<R> {
         })
     }
 
+    fn skip_to_objects(&mut self) -> Result<u16, DecodeError> {
+        let header = self.decode_header()?;
+
+        let texture_descriptors_size_bytes =
+            (header.texture_count as usize * TEXTURE_DESCRIPTOR_SIZE_BYTES) as u64;
+        io::copy(
+            &mut (&mut self.reader).take(texture_descriptors_size_bytes),
+            &mut io::sink(),
+        )?;
+
+        Ok(header.object_count)
+    }
+
     fn read_objects(&mut self, count: u16) -> Result<Vec<Object>, DecodeError> {
         let mut objects = Vec::with_capacity(count as usize);
 
@@ -235,3 +262,43 @@ impl<R: Read + Seek> Decoder<R> {
             .into_owned())
     }
 }
+
+/// An iterator over the objects of an M3D file. See [`Decoder::objects_iter`].
+pub struct ObjectsIter<R>
+where
+    R: Read,
+{
+    decoder: Decoder<R>,
+    /// The number of objects left to read, or `None` if the header has not
+    /// been read yet.
+    remaining: Option<u16>,
+}
+
+impl<R: Read> Iterator for ObjectsIter<R> {
+    type Item = Result<Object, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = match self.remaining {
+            Some(remaining) => remaining,
+            None => match self.decoder.skip_to_objects() {
+                Ok(count) => count,
+                Err(e) => {
+                    self.remaining = Some(0);
+                    return Some(Err(e));
+                }
+            },
+        };
+
+        if remaining == 0 {
+            self.remaining = Some(0);
+            return None;
+        }
+
+        let result = self.decoder.read_object();
+        // Stop after an error because the reader is left in an unknown
+        // position.
+        self.remaining = Some(if result.is_ok() { remaining - 1 } else { 0 });
+
+        Some(result)
+    }
+}
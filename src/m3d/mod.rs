@@ -24,6 +24,163 @@ pub struct M3d {
     pub objects: Vec<Object>,
 }
 
+impl M3d {
+    /// Decodes a model from the bytes of an `.M3D` or `.M3X` file.
+    pub fn from_slice(bytes: &[u8]) -> Result<M3d, DecodeError> {
+        Decoder::new(bytes).decode()
+    }
+
+    /// Returns the indices of the objects whose parent is the object at
+    /// `index`.
+    pub fn children_of(&self, index: usize) -> Vec<usize> {
+        self.objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| object.parent() == Some(index))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns the indices of the objects that have no parent.
+    pub fn root_objects(&self) -> Vec<usize> {
+        self.objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| object.parent().is_none())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns the translation of the object at `index` in model space, i.e.
+    /// the sum of its own translation and the translations of all of its
+    /// ancestors. Only objects with the
+    /// [`ObjectFlags::CUSTOM_TRANSLATION_ENABLED`] flag contribute a
+    /// translation.
+    ///
+    /// Returns [`Vec3::ZERO`] if `index` is out of bounds.
+    pub fn world_transform(&self, index: usize) -> Vec3 {
+        let mut translation = Vec3::ZERO;
+
+        let mut current = Some(index);
+        // Guard against malformed files where the parent chain loops.
+        let mut remaining = self.objects.len();
+
+        while let Some(i) = current {
+            let Some(object) = self.objects.get(i) else {
+                break;
+            };
+            if remaining == 0 {
+                break;
+            }
+            remaining -= 1;
+
+            if object
+                .flags
+                .contains(ObjectFlags::CUSTOM_TRANSLATION_ENABLED)
+            {
+                translation += object.translation;
+            }
+
+            current = object.parent();
+        }
+
+        translation
+    }
+
+    /// Returns the `(object_index, face_index)` pairs of the faces whose
+    /// stored normal points away from the normal given by their winding.
+    ///
+    /// The normal given by the winding follows the right-hand rule, i.e. the
+    /// vertices are counter-clockwise when looking at the front of the face.
+    /// Degenerate faces and faces with out of bounds indices are skipped.
+    pub fn check_face_windings(&self) -> Vec<(usize, usize)> {
+        self.objects
+            .iter()
+            .enumerate()
+            .flat_map(|(object_index, object)| {
+                object
+                    .faces
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, face)| object.is_face_winding_flipped(face))
+                    .map(move |(face_index, _)| (object_index, face_index))
+            })
+            .collect()
+    }
+
+    /// Flips the winding of the faces returned by
+    /// [`M3d::check_face_windings`] so that it matches their stored normal.
+    pub fn fix_face_windings(&mut self) {
+        for (object_index, face_index) in self.check_face_windings() {
+            self.objects[object_index].faces[face_index]
+                .indices
+                .swap(1, 2);
+        }
+    }
+
+    /// Returns counts and bounds for the model, e.g. to find unusually large
+    /// models when auditing assets.
+    pub fn stats(&self) -> M3dStats {
+        let (min, max) = self
+            .objects
+            .iter()
+            .enumerate()
+            .flat_map(|(index, object)| {
+                let translation = self.world_transform(index);
+                object
+                    .vertices
+                    .iter()
+                    .map(move |v| v.position + translation)
+            })
+            .fold(None, |bounds: Option<(Vec3, Vec3)>, position| {
+                Some(match bounds {
+                    Some((min, max)) => (min.min(position), max.max(position)),
+                    None => (position, position),
+                })
+            })
+            .unwrap_or((Vec3::ZERO, Vec3::ZERO));
+
+        M3dStats {
+            object_count: self.objects.len(),
+            texture_count: self.texture_descriptors.len(),
+            vertex_count: self.objects.iter().map(|o| o.vertices.len()).sum(),
+            face_count: self.objects.iter().map(|o| o.faces.len()).sum(),
+            min,
+            max,
+        }
+    }
+
+    /// Sets the CRC fields in the header to the values found in all of the
+    /// game's files: a CRC of 0 and its bitwise complement, 0xFFFFFFFF.
+    ///
+    /// The game does not seem to use the CRC and it is not known which
+    /// algorithm, if any, was used to compute it, so it cannot be recomputed
+    /// from the model data. This is useful for tools that generate M3D files
+    /// from scratch and want their headers to match the game's.
+    pub fn set_placeholder_crc(&mut self) {
+        self.header._crc = 0;
+        self.header._not_crc = !self.header._crc;
+    }
+}
+
+/// Counts and bounds for a model. See [`M3d::stats`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
+pub struct M3dStats {
+    pub object_count: usize,
+    pub texture_count: usize,
+    /// The total number of vertices across all objects.
+    pub vertex_count: usize,
+    /// The total number of faces across all objects.
+    pub face_count: usize,
+    /// The minimum corner of the model's axis-aligned bounding box, in model
+    /// space. This is [`Vec3::ZERO`] if the model has no vertices.
+    pub min: Vec3,
+    /// The maximum corner of the model's axis-aligned bounding box, in model
+    /// space. This is [`Vec3::ZERO`] if the model has no vertices.
+    pub max: Vec3,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 #[cfg_attr(feature = "bevy_reflect", reflect(opaque))]
@@ -74,6 +231,30 @@ impl M3dTextureDescriptor {
     pub fn is_color_keyed(&self) -> bool {
         self.file_name.to_ascii_lowercase().starts_with("_1")
     }
+
+    /// Returns `true` if the texture descriptor indicates that the texture is
+    /// animated. See [`M3dTextureDescriptor::is_water`].
+    pub fn is_animated(&self) -> bool {
+        self.is_water()
+    }
+
+    /// Returns `true` if the texture descriptor indicates that the texture is
+    /// transparent. See [`M3dTextureDescriptor::is_water`].
+    pub fn is_transparent(&self) -> bool {
+        self.is_water()
+    }
+
+    /// Returns `true` if the texture is a water (or jewel) texture, i.e. it has
+    /// the `_2` prefix.
+    ///
+    /// There is no separate flag for animation or transparency. The game
+    /// renders every water texture both animated and partially transparent, so
+    /// [`M3dTextureDescriptor::is_animated`] and
+    /// [`M3dTextureDescriptor::is_transparent`] are both this check. They are
+    /// kept apart so callers can say which of the two they care about.
+    pub fn is_water(&self) -> bool {
+        self.file_name.to_ascii_lowercase().starts_with("_2")
+    }
 }
 
 bitflags! {
@@ -90,6 +271,8 @@ bitflags! {
     }
 }
 
+crate::flags::impl_unknown_bits_set!(ObjectFlags);
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 #[cfg_attr(feature = "bevy_reflect", reflect(opaque))]
@@ -112,6 +295,53 @@ pub struct Object {
     pub vertices: Vec<Vertex>,
 }
 
+impl Object {
+    /// Returns the index of the parent object, or `None` if the object is a
+    /// root object.
+    pub fn parent(&self) -> Option<usize> {
+        if self.parent_index < 0 {
+            None
+        } else {
+            Some(self.parent_index as usize)
+        }
+    }
+
+    /// Returns the minimum and maximum UVs of the object's vertices.
+    ///
+    /// Returns `(Vec2::ZERO, Vec2::ZERO)` if the object has no vertices.
+    pub fn uv_bounds(&self) -> (Vec2, Vec2) {
+        if self.vertices.is_empty() {
+            return (Vec2::ZERO, Vec2::ZERO);
+        }
+
+        self.vertices.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), v| (min.min(v.uv), max.max(v.uv)),
+        )
+    }
+
+    /// Returns `true` if any of the object's UVs are outside of the `[0, 1]`
+    /// range, i.e. its textures need to repeat and it cannot use a texture
+    /// from a non-repeating atlas without artifacts.
+    pub fn needs_wrapping(&self) -> bool {
+        let (min, max) = self.uv_bounds();
+        min.cmplt(Vec2::ZERO).any() || max.cmpgt(Vec2::ONE).any()
+    }
+
+    /// Returns `true` if the face's stored normal points away from the normal
+    /// given by its winding.
+    fn is_face_winding_flipped(&self, face: &Face) -> bool {
+        let [a, b, c] = face
+            .indices
+            .map(|i| self.vertices.get(i as usize).map(|v| v.position));
+        let (Some(a), Some(b), Some(c)) = (a, b, c) else {
+            return false;
+        };
+
+        (b - a).cross(c - a).dot(face.normal) < 0.
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 #[cfg_attr(feature = "bevy_reflect", reflect(opaque))]
@@ -186,18 +416,196 @@ mod tests {
         assert_eq!(original_bytes, encoded_bytes);
     }
 
+    #[test]
+    fn test_texture_descriptor_prefixes() {
+        let d = |file_name: &str| M3dTextureDescriptor {
+            file_name: file_name.to_string(),
+            ..Default::default()
+        };
+
+        assert!(d("_1WOOD8.bmp").is_color_keyed());
+        assert!(!d("_1WOOD8.bmp").is_water());
+        assert!(!d("_1WOOD8.bmp").is_animated());
+        assert!(!d("_1WOOD8.bmp").is_transparent());
+
+        assert!(!d("_2wtpool.bmp").is_color_keyed());
+        assert!(d("_2wtpool.bmp").is_water());
+        assert!(d("_2wtpool.bmp").is_animated());
+        assert!(d("_2wtpool.bmp").is_transparent());
+
+        assert!(!d("nflgrs01.bmp").is_color_keyed());
+        assert!(!d("nflgrs01.bmp").is_water());
+        assert!(!d("nflgrs01.bmp").is_animated());
+        assert!(!d("nflgrs01.bmp").is_transparent());
+    }
+
+    #[test]
+    fn test_object_hierarchy() {
+        let m = M3d {
+            objects: vec![
+                Object {
+                    parent_index: -1,
+                    translation: Vec3::new(1., 2., 3.),
+                    flags: ObjectFlags::CUSTOM_TRANSLATION_ENABLED,
+                    ..Default::default()
+                },
+                Object {
+                    parent_index: 0,
+                    translation: Vec3::new(10., 20., 30.),
+                    flags: ObjectFlags::CUSTOM_TRANSLATION_ENABLED,
+                    ..Default::default()
+                },
+                Object {
+                    parent_index: 1,
+                    translation: Vec3::new(100., 200., 300.),
+                    ..Default::default()
+                },
+                Object {
+                    parent_index: -1,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(m.root_objects(), vec![0, 3]);
+        assert_eq!(m.children_of(0), vec![1]);
+        assert_eq!(m.children_of(1), vec![2]);
+        assert!(m.children_of(2).is_empty());
+
+        assert_eq!(m.world_transform(0), Vec3::new(1., 2., 3.));
+        assert_eq!(m.world_transform(1), Vec3::new(11., 22., 33.));
+        // Translation is not enabled for the object itself.
+        assert_eq!(m.world_transform(2), Vec3::new(11., 22., 33.));
+        assert_eq!(m.world_transform(3), Vec3::ZERO);
+        assert_eq!(m.world_transform(4), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_uv_bounds() {
+        let vertex = |u, v| Vertex {
+            uv: Vec2::new(u, v),
+            ..Default::default()
+        };
+
+        let mut object = Object {
+            vertices: vec![vertex(0.25, 0.5), vertex(1., 0.), vertex(0.5, 0.75)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            object.uv_bounds(),
+            (Vec2::new(0.25, 0.), Vec2::new(1., 0.75))
+        );
+        assert!(!object.needs_wrapping());
+
+        object.vertices.push(vertex(-0.5, 2.));
+
+        assert_eq!(object.uv_bounds(), (Vec2::new(-0.5, 0.), Vec2::new(1., 2.)));
+        assert!(object.needs_wrapping());
+
+        assert_eq!(Object::default().uv_bounds(), (Vec2::ZERO, Vec2::ZERO));
+        assert!(!Object::default().needs_wrapping());
+    }
+
+    #[test]
+    fn test_stats() {
+        let vertex = |x, y, z| Vertex {
+            position: Vec3::new(x, y, z),
+            ..Default::default()
+        };
+
+        let m = M3d {
+            texture_descriptors: vec![Default::default()],
+            objects: vec![
+                Object {
+                    parent_index: -1,
+                    faces: vec![Default::default()],
+                    vertices: vec![vertex(-1., 0., 0.), vertex(1., 2., 0.), vertex(0., 0., 3.)],
+                    ..Default::default()
+                },
+                Object {
+                    parent_index: 0,
+                    translation: Vec3::new(0., 10., 0.),
+                    flags: ObjectFlags::CUSTOM_TRANSLATION_ENABLED,
+                    vertices: vec![vertex(0., 0., 0.)],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            m.stats(),
+            M3dStats {
+                object_count: 2,
+                texture_count: 1,
+                vertex_count: 4,
+                face_count: 1,
+                min: Vec3::new(-1., 0., 0.),
+                max: Vec3::new(1., 10., 3.),
+            }
+        );
+        assert_eq!(M3d::default().stats(), M3dStats::default());
+    }
+
+    #[test]
+    fn test_face_windings() {
+        let vertex = |x, y, z| Vertex {
+            position: Vec3::new(x, y, z),
+            ..Default::default()
+        };
+
+        let mut m = M3d {
+            objects: vec![Object {
+                vertices: vec![vertex(0., 0., 0.), vertex(1., 0., 0.), vertex(0., 1., 0.)],
+                faces: vec![
+                    Face {
+                        indices: [0, 1, 2],
+                        normal: Vec3::Z,
+                        ..Default::default()
+                    },
+                    Face {
+                        indices: [0, 1, 2],
+                        normal: Vec3::NEG_Z,
+                        ..Default::default()
+                    },
+                    Face {
+                        indices: [0, 1, 3],
+                        normal: Vec3::NEG_Z,
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(m.check_face_windings(), vec![(0, 1)]);
+
+        m.fix_face_windings();
+
+        assert_eq!(m.objects[0].faces[1].indices, [0, 2, 1]);
+        assert!(m.check_face_windings().is_empty());
+    }
+
+    #[test]
+    fn test_set_placeholder_crc() {
+        let mut m = M3d::default();
+
+        m.set_placeholder_crc();
+
+        assert_eq!(m.header._crc, 0);
+        assert_eq!(m.header._not_crc, 4294967295);
+    }
+
     #[test]
     fn test_decode_b1_01_base() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B1_01",
-            "BASE.M3D",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("BASE.M3D");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -210,11 +618,45 @@ mod tests {
         roundtrip_test(&original_bytes, &m3d);
     }
 
+    #[test]
+    fn test_objects_iter_b1_01_base() {
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("BASE.M3D");
+
+        let m3d = Decoder::new(File::open(d.clone()).unwrap())
+            .decode()
+            .unwrap();
+
+        let objects = Decoder::new(File::open(d).unwrap())
+            .objects_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(objects.len(), m3d.objects.len());
+        for (object, expected) in objects.iter().zip(m3d.objects.iter()) {
+            assert_eq!(object.name, expected.name);
+            assert_eq!(object.faces.len(), expected.faces.len());
+            assert_eq!(object.vertices.len(), expected.vertices.len());
+        }
+    }
+
+    #[test]
+    fn test_objects_iter_invalid_format() {
+        let mut objects = Decoder::new(std::io::Cursor::new(vec![0; 24])).objects_iter();
+
+        assert!(matches!(
+            objects.next(),
+            Some(Err(DecodeError::InvalidFormat(_)))
+        ));
+        assert!(objects.next().is_none());
+    }
+
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [std::env::var("DARKOMEN_PATH").unwrap().as_str(), "DARKOMEN"]
-            .iter()
-            .collect();
+        let d: PathBuf = crate::game_path().unwrap();
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "m3ds"]
             .iter()
@@ -286,7 +728,7 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &m3d, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &m3d, crate::ron_config()).unwrap();
         });
     }
 
@@ -14,6 +14,7 @@ pub struct Cli {
 pub enum Subcommands {
     Army(cli::army::ArmyArgs),
     Project(cli::project::ProjectArgs),
+    SpriteSheet(cli::sprite_sheet::SpriteSheetArgs),
 }
 
 fn main() -> Result<()> {
@@ -22,6 +23,7 @@ fn main() -> Result<()> {
     match cli.subcommand {
         Subcommands::Army(args) => cli::army::run(&args)?,
         Subcommands::Project(args) => cli::project::run(&args)?,
+        Subcommands::SpriteSheet(args) => cli::sprite_sheet::run(&args)?,
     }
 
     Ok(())
@@ -2,7 +2,7 @@ use super::*;
 use std::{
     ffi::CStr,
     fmt,
-    io::{Error as IoError, Read, Seek},
+    io::{Error as IoError, Read},
     mem::size_of,
 };
 
@@ -84,14 +84,15 @@ impl fmt::Display for DecodeError {
     }
 }
 
+/// Decodes a project from a `.PRJ` file.
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
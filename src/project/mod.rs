@@ -1,15 +1,22 @@
 mod decoder;
 mod encoder;
+mod scene;
 
 #[cfg(feature = "bevy_reflect")]
 use bevy_reflect::prelude::*;
 use bitflags::bitflags;
-use glam::{DVec3, Vec3};
+use glam::{DVec3, EulerRot, Quat, UVec2, Vec2, Vec3};
 use image::{DynamicImage, GenericImage, Rgba};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
 
 pub use decoder::{DecodeError, Decoder};
 pub use encoder::{EncodeError, Encoder};
+pub use scene::{Scene, SceneError};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
@@ -18,6 +25,7 @@ pub struct Project {
     ///
     /// The file name is relative to the directory where the project file is
     /// located.
+    #[serde(rename = "base_model_file_name")]
     pub base_model_file_name: String,
     /// The water model file name, including the extension. E.g. `_7water.M3D`.
     /// If not present, the project has no water model.
@@ -27,28 +35,42 @@ pub struct Project {
     ///
     /// Note: Some projects overload this field for other non-water models. E.g.
     /// in B1_07 this field is `_4tower.m3d` to render a tower instead of water.
+    #[serde(rename = "water_model_file_name")]
     pub water_model_file_name: Option<String>,
     /// A list of furniture model file names, including the extension. This is
     /// used by instances to look up the model they use.
     ///
     /// The file names are relative to the directory where the project file is
     /// located.
+    #[serde(rename = "furniture_model_file_names")]
     pub furniture_model_file_names: Vec<String>,
+    #[serde(rename = "instances")]
     pub instances: Vec<Instance>,
+    #[serde(rename = "terrain")]
     pub terrain: Terrain,
+    #[serde(rename = "attributes")]
     pub attributes: Attributes,
     #[cfg_attr(feature = "bevy_reflect", reflect(ignore))]
+    #[serde(rename = "excl")]
     excl: Excl,
     /// The music script file name, including the extension. E.g. `battle1.fsm`.
     /// This can be used to play background music during a battle, or on various
     /// UI screens.
+    #[serde(rename = "music_script_file_name")]
     pub music_script_file_name: String,
+    #[serde(rename = "tracks")]
     pub tracks: Vec<Track>,
     #[cfg_attr(feature = "bevy_reflect", reflect(ignore))]
+    #[serde(rename = "edit")]
     edit: Vec<u8>,
 }
 
 impl Project {
+    /// Decodes a project from the bytes of a `.PRJ` file.
+    pub fn from_slice(bytes: &[u8]) -> Result<Project, DecodeError> {
+        Decoder::new(bytes).decode()
+    }
+
     /// Get the base model file name, including the extension, but with the
     /// extension replaced with `.M3X`. E.g. `base.M3D` becomes `base.M3X`.
     ///
@@ -71,51 +93,349 @@ impl Project {
             .as_ref()
             .map(|s| s.replace(".m3d", ".m3x").replace(".M3D", ".M3X"))
     }
+
+    /// Returns the contents of the project's `EXCL` block.
+    ///
+    /// The purpose of the block is not known yet.
+    pub fn excl(&self) -> &Excl {
+        &self.excl
+    }
+
+    /// Returns the raw contents of the project's `EDIT` block, excluding the
+    /// block ID.
+    ///
+    /// The format of the block is not known yet, but it is likely metadata
+    /// used by the original editor.
+    pub fn edit(&self) -> &[u8] {
+        &self.edit
+    }
+
+    /// Returns the paths of the models used by the project, resolved relative
+    /// to `project_dir`, the directory where the project file is located.
+    pub fn resolve_model_paths(&self, project_dir: &Path) -> ResolvedModels {
+        ResolvedModels {
+            base: project_dir.join(&self.base_model_file_name),
+            base_m3x: project_dir.join(self.get_base_m3x_model_file_name()),
+            water: self
+                .water_model_file_name
+                .as_ref()
+                .map(|file_name| project_dir.join(file_name)),
+            water_m3x: self
+                .get_water_m3x_model_file_name()
+                .map(|file_name| project_dir.join(file_name)),
+            furniture: self
+                .furniture_model_file_names
+                .iter()
+                .map(|file_name| project_dir.join(file_name))
+                .collect(),
+        }
+    }
+
+    /// Returns an iterator over the instances that can be attacked.
+    pub fn attackable_instances(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.iter().filter(|i| i.is_attackable())
+    }
+
+    /// Returns an iterator over the instances that can be set on fire.
+    pub fn burnable_instances(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.iter().filter(|i| i.is_burnable())
+    }
+
+    /// Sets the width and height in the project's attributes to the width and
+    /// height of its terrain.
+    ///
+    /// Returns an error, and leaves the project unchanged, if either
+    /// heightmap does not have exactly one block for each 8x8 area of the
+    /// terrain.
+    pub fn sync_dimensions(&mut self) -> Result<(), DimensionsError> {
+        let expected_block_count =
+            (self.terrain.width_in_blocks() * self.terrain.height_in_blocks()) as usize;
+
+        if self.terrain.heightmap1_blocks.len() != expected_block_count
+            || self.terrain.heightmap2_blocks.len() != expected_block_count
+        {
+            return Err(DimensionsError {
+                expected_block_count,
+                heightmap1_block_count: self.terrain.heightmap1_blocks.len(),
+                heightmap2_block_count: self.terrain.heightmap2_blocks.len(),
+            });
+        }
+
+        self.attributes.width = self.terrain.width;
+        self.attributes.height = self.terrain.height;
+
+        Ok(())
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// The error returned by [`Project::sync_dimensions`] when the terrain's
+/// heightmaps do not match its dimensions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DimensionsError {
+    /// The number of blocks each heightmap should have.
+    pub expected_block_count: usize,
+    pub heightmap1_block_count: usize,
+    pub heightmap2_block_count: usize,
+}
+
+impl std::error::Error for DimensionsError {}
+
+impl fmt::Display for DimensionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "heightmap block count mismatch: expected {} blocks, found {} and {}",
+            self.expected_block_count, self.heightmap1_block_count, self.heightmap2_block_count
+        )
+    }
+}
+
+/// The error returned by [`Terrain::apply_heightmap_image`] when the image
+/// does not match the terrain's dimensions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HeightmapImageError {
+    pub expected_width: u32,
+    pub expected_height: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl std::error::Error for HeightmapImageError {}
+
+impl fmt::Display for HeightmapImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "heightmap image size mismatch: expected {}x{}, found {}x{}",
+            self.expected_width, self.expected_height, self.width, self.height
+        )
+    }
+}
+
+/// The paths of the models used by a project. See
+/// [`Project::resolve_model_paths`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResolvedModels {
+    /// The path of the base model.
+    pub base: PathBuf,
+    /// The path of the chunked M3X version of the base model. This is the one
+    /// rendered in game.
+    pub base_m3x: PathBuf,
+    /// The path of the water model, if any.
+    pub water: Option<PathBuf>,
+    /// The path of the chunked M3X version of the water model, if any.
+    pub water_m3x: Option<PathBuf>,
+    /// The paths of the furniture models, in the same order as
+    /// [`Project::furniture_model_file_names`].
+    pub furniture: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Instance {
+    #[serde(rename = "prev")]
     prev: i32,
+    #[serde(rename = "next")]
     next: i32,
+    #[serde(rename = "selected")]
     selected: i32,
+    #[serde(rename = "exclude_from_terrain")]
     pub exclude_from_terrain: i32,
+    #[serde(rename = "position")]
     pub position: DVec3,
+    #[serde(rename = "rotation")]
     pub rotation: DVec3,
+    /// The minimum corner of the instance's axis-aligned bounding box. The box
+    /// is in the project's world space, i.e. the instance's position and
+    /// rotation are already applied. See [`Instance::world_aabb`].
+    #[serde(rename = "aabb_min")]
     pub aabb_min: DVec3,
+    /// The maximum corner of the instance's axis-aligned bounding box. See
+    /// [`Instance::aabb_min`].
+    #[serde(rename = "aabb_max")]
     pub aabb_max: DVec3,
     /// Slot is 1-based, not 0-based. A slot of 1 refers to the first furniture
     /// model and a slot of 0 means the instance is not used.
+    #[serde(rename = "furniture_model_slot")]
     pub furniture_model_slot: u32,
+    #[serde(rename = "model_id")]
     model_id: i32,
+    #[serde(rename = "attackable")]
     attackable: i32,
+    #[serde(rename = "toughness")]
     toughness: i32,
+    #[serde(rename = "wounds")]
     wounds: i32,
+    #[serde(rename = "unknown1")]
     pub unknown1: i32,
+    #[serde(rename = "owner_unit_index")]
     owner_unit_index: i32,
+    #[serde(rename = "burnable")]
     burnable: i32,
+    #[serde(rename = "sfx_code")]
     pub sfx_code: u32,
     /// Instances with a model can have a GFX code set, e.g. for the windmill
     /// model, it has animated sails and for some building models they have an
     /// animated flag or sign.
+    #[serde(rename = "gfx_code")]
     pub gfx_code: u32,
+    #[serde(rename = "locked")]
     locked: i32,
+    #[serde(rename = "exclude_from_terrain_shadow")]
     exclude_from_terrain_shadow: i32,
+    #[serde(rename = "exclude_from_walk")]
     exclude_from_walk: i32,
+    #[serde(rename = "magic_item_id")]
     pub magic_item_id: u32,
+    #[serde(rename = "particle_effect_code")]
     pub particle_effect_code: u32,
     /// Slot is 1-based, not 0-based. A slot of 1 refers to the first furniture
     /// model and a slot of 0 means the instance is not used.
+    #[serde(rename = "furniture_dead_model_slot")]
     pub furniture_dead_model_slot: u32,
+    #[serde(rename = "dead_model_id")]
     dead_model_id: i32,
+    #[serde(rename = "light")]
     pub light: i32,
+    #[serde(rename = "light_radius")]
     light_radius: i32,
+    #[serde(rename = "light_ambient")]
     light_ambient: i32,
+    #[serde(rename = "unknown2")]
     pub unknown2: i32,
+    #[serde(rename = "unknown3")]
     pub unknown3: i32,
 }
 
-#[derive(Clone, Debug, Serialize)]
+impl Instance {
+    /// Returns `true` if the instance can be attacked.
+    #[inline(always)]
+    pub fn is_attackable(&self) -> bool {
+        self.attackable != 0
+    }
+
+    /// Returns `true` if the instance can be set on fire.
+    #[inline(always)]
+    pub fn is_burnable(&self) -> bool {
+        self.burnable != 0
+    }
+
+    /// Returns `true` if the instance is locked.
+    #[inline(always)]
+    pub fn is_locked(&self) -> bool {
+        self.locked != 0
+    }
+
+    /// Returns `true` if units cannot walk through the instance.
+    #[inline(always)]
+    pub fn is_excluded_from_walk(&self) -> bool {
+        self.exclude_from_walk != 0
+    }
+
+    /// Returns `true` if the instance is not part of the terrain.
+    #[inline(always)]
+    pub fn is_excluded_from_terrain(&self) -> bool {
+        self.exclude_from_terrain != 0
+    }
+
+    /// Returns `true` if the instance has an animation, i.e. it has a GFX code
+    /// set. See [`Instance::gfx_code`].
+    ///
+    /// Note: The meaning of the individual GFX codes is not known yet, so it
+    /// is not possible to tell which animation, e.g. windmill sails or a flag,
+    /// the instance has.
+    #[inline(always)]
+    pub fn has_animation(&self) -> bool {
+        self.gfx_code != 0
+    }
+
+    /// Returns `true` if the instance has a SFX code set.
+    #[inline(always)]
+    pub fn has_sfx(&self) -> bool {
+        self.sfx_code != 0
+    }
+
+    /// Returns `true` if the instance has a particle effect code set.
+    #[inline(always)]
+    pub fn has_particle_effect(&self) -> bool {
+        self.particle_effect_code != 0
+    }
+
+    /// Returns the translation, rotation and scale of the instance in the
+    /// project's coordinate space.
+    ///
+    /// The rotation is stored as angles around the x, y and z axes in turns,
+    /// i.e. 1.0 is a full turn. They are applied in YXZ order: yaw around the
+    /// y-axis first, then pitch, then roll. The AABB describes the bounds of
+    /// the instance rather than a scale, so the scale is always
+    /// [`Vec3::ONE`].
+    pub fn transform(&self) -> (Vec3, Quat, Vec3) {
+        let rotation = self.rotation.as_vec3() * std::f32::consts::TAU;
+
+        (
+            self.position.as_vec3(),
+            Quat::from_euler(EulerRot::YXZ, rotation.y, rotation.x, rotation.z),
+            Vec3::ONE,
+        )
+    }
+
+    /// Returns the minimum and maximum corners of the instance's axis-aligned
+    /// bounding box in the project's coordinate space.
+    ///
+    /// The stored box is already in world space, so unlike
+    /// [`Instance::transform`] no position or rotation needs to be applied. A
+    /// box in model space would no longer be axis-aligned once the instance's
+    /// rotation is applied.
+    pub fn world_aabb(&self) -> (Vec3, Vec3) {
+        (self.aabb_min.as_vec3(), self.aabb_max.as_vec3())
+    }
+
+    /// Returns the file name of the furniture model the instance uses, or
+    /// `None` if it has no model.
+    pub fn furniture_model_file_name<'a>(&self, project: &'a Project) -> Option<&'a str> {
+        model_file_name(project, self.furniture_model_slot)
+    }
+
+    /// Returns the file name of the furniture model the instance uses once it
+    /// is destroyed, e.g. the rubble of a burnt down building, or `None` if it
+    /// has no dead model.
+    pub fn dead_furniture_model_file_name<'a>(&self, project: &'a Project) -> Option<&'a str> {
+        model_file_name(project, self.furniture_dead_model_slot)
+    }
+
+    /// Switches the instance to its dead model, if it has one.
+    ///
+    /// The dead model becomes the instance's model and the instance no longer
+    /// has a dead model, so calling this more than once has no further
+    /// effect.
+    pub fn become_dead(&mut self) {
+        if self.furniture_dead_model_slot == 0 {
+            return;
+        }
+
+        self.furniture_model_slot = self.furniture_dead_model_slot;
+        self.model_id = self.dead_model_id;
+        self.furniture_dead_model_slot = 0;
+        self.dead_model_id = 0;
+    }
+
+    /// Returns `true` if the instance has a magic item.
+    #[inline(always)]
+    pub fn has_magic_item(&self) -> bool {
+        self.magic_item_id != 0
+    }
+}
+
+/// Returns the file name of the furniture model in the 1-based `slot`.
+fn model_file_name(project: &Project, slot: u32) -> Option<&str> {
+    let index = slot.checked_sub(1)? as usize;
+    project
+        .furniture_model_file_names
+        .get(index)
+        .map(String::as_str)
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum Heightmap {
     /// The heightmap that includes the base terrain and furniture instances
     /// like buildings.
@@ -156,7 +476,6 @@ impl Terrain {
     /// Returns the height of the terrain in blocks. That is, how many 8x8
     /// blocks are needed to cover the height of the terrain.
     #[inline]
-    #[allow(dead_code)]
     fn height_in_blocks(&self) -> u32 {
         (self.height + 7) / 8 // adding 7 and dividing by 8 is equivalent to ceil division
     }
@@ -250,11 +569,6 @@ impl Terrain {
         // base height is below (u16::MAX - u8::MAX) so this is not a concern.
         const MAX_NORMALIZED_HEIGHT: f32 = u16::MAX as f32 / 1024.;
 
-        // The largest value that can be stored for a block's offset height is
-        // u8::MAX because offset height is a u8. u8::MAX is then divided by 8
-        // to get the normalized maximum.
-        const MAX_NORMALIZED_OFFSET_HEIGHT: f32 = u8::MAX as f32 / 8.;
-
         let normalized_height = block.normalized_base_height() + normalized_offset_height;
 
         let scaled_value = normalized_height / MAX_NORMALIZED_HEIGHT;
@@ -272,6 +586,112 @@ impl Terrain {
         color as u8 // truncate any fractional part
     }
 
+    /// Replaces the heights of the given heightmap with the ones in a grayscale
+    /// image, e.g. one exported with [`Terrain::base_heightmap_image`] and then
+    /// edited.
+    ///
+    /// This is the inverse of the heightmap image export: the image is flipped
+    /// back horizontally and each gray level is mapped back to a height in the
+    /// same range that the export uses, i.e. from the lowest block base height
+    /// to the highest block base height plus the maximum offset height. Each
+    /// block's base height is then set to the lowest height in the block and
+    /// its height offsets are rebuilt relative to it. Height offsets that end
+    /// up duplicated or unused are removed with
+    /// [`Terrain::rebuild_height_offsets`].
+    ///
+    /// Note: The round trip is lossy. The image only has 256 gray levels to
+    /// cover the whole height range, the export truncates rather than rounds,
+    /// and height offsets can only be stored in steps of 1/8. Heights within a
+    /// block that are more than the maximum offset height above the block's
+    /// lowest height are clamped.
+    ///
+    /// Returns an error, and leaves the terrain unchanged, if the image does
+    /// not have the same dimensions as the terrain.
+    pub fn apply_heightmap_image(
+        &mut self,
+        map: Heightmap,
+        img: &DynamicImage,
+    ) -> Result<(), HeightmapImageError> {
+        if img.width() != self.width || img.height() != self.height {
+            return Err(HeightmapImageError {
+                expected_width: self.width,
+                expected_height: self.height,
+                width: img.width(),
+                height: img.height(),
+            });
+        }
+
+        let img = img.fliph().to_luma8();
+        let width_in_blocks = self.width_in_blocks();
+
+        let blocks = match map {
+            Heightmap::Furniture => &self.heightmap1_blocks,
+            Heightmap::Base => &self.heightmap2_blocks,
+        };
+
+        let (min_normalized_base_height, max_normalized_base_height) =
+            Terrain::min_and_max_normalized_base_height(blocks);
+        let min = min_normalized_base_height;
+        let max = max_normalized_base_height + MAX_NORMALIZED_OFFSET_HEIGHT;
+
+        let mut new_blocks = Vec::with_capacity(blocks.len());
+
+        for i in 0..blocks.len() as u32 {
+            let col = i % width_in_blocks;
+            let row = i / width_in_blocks;
+
+            // Cells that are outside of the terrain are left at the block's
+            // base height.
+            let mut heights = [None; 64];
+            for y in 0..8 {
+                for x in 0..8 {
+                    let target_x = col * 8 + x;
+                    let target_y = row * 8 + y;
+
+                    if target_x < self.width && target_y < self.height {
+                        let color = img.get_pixel(target_x, target_y)[0];
+                        heights[(x + y * 8) as usize] =
+                            Some(min + color as f32 / 255. * (max - min));
+                    }
+                }
+            }
+
+            let lowest = heights.iter().flatten().copied().fold(f32::MAX, f32::min);
+            let base_height = if lowest == f32::MAX {
+                0
+            } else {
+                (lowest * 1024.).floor() as i32
+            };
+
+            let block = TerrainBlock {
+                base_height,
+                height_offsets_index: self.height_offsets.len() as u32,
+            };
+
+            let height_offsets = heights
+                .iter()
+                .map(|height| match height {
+                    Some(height) => ((height - block.normalized_base_height()) * 8.)
+                        .round()
+                        .clamp(0., u8::MAX as f32) as u8,
+                    None => 0,
+                })
+                .collect();
+
+            self.height_offsets.push(height_offsets);
+            new_blocks.push(block);
+        }
+
+        match map {
+            Heightmap::Furniture => self.heightmap1_blocks = new_blocks,
+            Heightmap::Base => self.heightmap2_blocks = new_blocks,
+        }
+
+        self.rebuild_height_offsets();
+
+        Ok(())
+    }
+
     pub fn height_at_world_position(&self, map: Heightmap, x: f32, y: f32) -> f32 {
         // Clamp the coordinates to the bounds of the terrain. In this way, any
         // coordinates that are out of bounds essentially get the height at the
@@ -301,6 +721,173 @@ impl Terrain {
 
         block.normalized_base_height() + Terrain::normalized_offset_height(offset_height)
     }
+
+    /// Returns the terrain as a heightfield, e.g. for use as a collider with a
+    /// physics engine.
+    ///
+    /// Returns the heights of every cell in row-major order, i.e. the height of
+    /// the cell at `(x, y)` is at index `x + y * width`, along with the grid
+    /// dimensions in cells and the size of a cell in world space. The heights
+    /// are the same as [`Terrain::height_at_world_position`] at each cell.
+    ///
+    /// Each cell is 1 unit in world space, which is
+    /// [`crate::battle_tabletop::SCALE`] units on the battle tabletop.
+    pub fn to_heightfield(&self, map: Heightmap) -> (Vec<f32>, UVec2, Vec2) {
+        let heights = (0..self.height)
+            .flat_map(|y| {
+                (0..self.width).map(move |x| self.height_at_world_position(map, x as f32, y as f32))
+            })
+            .collect();
+
+        (heights, UVec2::new(self.width, self.height), Vec2::ONE)
+    }
+
+    /// Returns the steepness of the terrain at the given world position, i.e.
+    /// the magnitude of the height gradient.
+    ///
+    /// The position is in terrain world units, where each cell is 1 unit, i.e.
+    /// a battle tabletop position divided by [`crate::battle_tabletop::SCALE`].
+    /// A slope of 0 means the terrain is flat and a slope of 1 means the height
+    /// changes by 1 for every cell moved horizontally. See
+    /// [`Terrain::gradient_at_world_position`].
+    pub fn slope_at_world_position(&self, map: Heightmap, x: f32, y: f32) -> f32 {
+        self.gradient_at_world_position(map, x, y).length()
+    }
+
+    /// Returns the unit normal of the terrain at the given world position.
+    ///
+    /// The normal is in a Y-up space where the terrain's x-axis is the x-axis
+    /// and the terrain's y-axis is the z-axis.
+    pub fn normal_at_world_position(&self, map: Heightmap, x: f32, y: f32) -> Vec3 {
+        let gradient = self.gradient_at_world_position(map, x, y);
+        Vec3::new(-gradient.x, 1., -gradient.y).normalize()
+    }
+
+    /// Returns the rate of change of the terrain height along the x-axis and
+    /// y-axis at the given world position.
+    ///
+    /// The gradient is computed from the heights of the neighboring cells.
+    /// Like [`Terrain::height_at_world_position`], each cell is 1 unit in world
+    /// space, i.e. the world position has already been divided by
+    /// [`crate::battle_tabletop::SCALE`]. Cells on the edge of the terrain use
+    /// the difference between themselves and their only neighbor. An empty
+    /// terrain is flat.
+    pub fn gradient_at_world_position(&self, map: Heightmap, x: f32, y: f32) -> Vec2 {
+        if self.width == 0 || self.height == 0 {
+            return Vec2::ZERO;
+        }
+
+        let x = (x as i32).clamp(0, self.width as i32 - 1);
+        let y = (y as i32).clamp(0, self.height as i32 - 1);
+
+        let difference = |from: (i32, i32), to: (i32, i32), distance: i32| {
+            if distance == 0 {
+                return 0.;
+            }
+            let from = self.height_at_world_position(map, from.0 as f32, from.1 as f32);
+            let to = self.height_at_world_position(map, to.0 as f32, to.1 as f32);
+            (to - from) / distance as f32
+        };
+
+        let (left, right) = ((x - 1).max(0), (x + 1).min(self.width as i32 - 1));
+        let (up, down) = ((y - 1).max(0), (y + 1).min(self.height as i32 - 1));
+
+        Vec2::new(
+            difference((left, y), (right, y), right - left),
+            difference((x, up), (x, down), down - up),
+        )
+    }
+
+    /// Sets the height offset of the cell at the given position in the given
+    /// heightmap.
+    ///
+    /// Height offsets can be shared between blocks, so if the block's height
+    /// offsets are used by any other block, they are copied first so that the
+    /// other blocks are not affected. Use [`Terrain::rebuild_height_offsets`]
+    /// to remove any duplicates created by editing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside of the terrain.
+    pub fn set_height_offset_at(&mut self, map: Heightmap, x: u32, y: u32, offset: u8) {
+        assert!(x < self.width && y < self.height, "position out of bounds");
+
+        let block_index = ((y >> 3) * self.width_in_blocks() + (x >> 3)) as usize;
+        let height_offsets_index = ((y % 8) * 8 + (x % 8)) as usize;
+
+        let current = match map {
+            Heightmap::Furniture => self.heightmap1_blocks[block_index].height_offsets_index,
+            Heightmap::Base => self.heightmap2_blocks[block_index].height_offsets_index,
+        };
+
+        let share_count = self
+            .heightmap1_blocks
+            .iter()
+            .chain(self.heightmap2_blocks.iter())
+            .filter(|block| block.height_offsets_index == current)
+            .count();
+
+        let index = if share_count > 1 {
+            self.height_offsets
+                .push(self.height_offsets[current as usize].clone());
+            let index = (self.height_offsets.len() - 1) as u32;
+
+            let block = match map {
+                Heightmap::Furniture => &mut self.heightmap1_blocks[block_index],
+                Heightmap::Base => &mut self.heightmap2_blocks[block_index],
+            };
+            block.height_offsets_index = index;
+
+            index
+        } else {
+            current
+        };
+
+        self.height_offsets[index as usize][height_offsets_index] = offset;
+    }
+
+    /// Removes duplicate and unused height offsets and updates the blocks of
+    /// both heightmaps to point at the remaining ones.
+    ///
+    /// The order of the remaining height offsets is kept, so terrain that has
+    /// no duplicate or unused height offsets is left unchanged.
+    pub fn rebuild_height_offsets(&mut self) {
+        let used = self
+            .heightmap1_blocks
+            .iter()
+            .chain(self.heightmap2_blocks.iter())
+            .map(|block| block.height_offsets_index as usize)
+            .collect::<HashSet<_>>();
+
+        let mut height_offsets = Vec::new();
+        let mut canonical_indices = HashMap::new();
+        let mut remapped_indices = HashMap::new();
+
+        for (i, offsets) in self.height_offsets.iter().enumerate() {
+            if !used.contains(&i) {
+                continue;
+            }
+
+            let index = *canonical_indices.entry(offsets).or_insert_with(|| {
+                height_offsets.push(offsets.clone());
+                (height_offsets.len() - 1) as u32
+            });
+            remapped_indices.insert(i as u32, index);
+        }
+
+        for block in self
+            .heightmap1_blocks
+            .iter_mut()
+            .chain(self.heightmap2_blocks.iter_mut())
+        {
+            // Indices that are out of bounds are left as they are.
+            if let Some(&index) = remapped_indices.get(&block.height_offsets_index) {
+                block.height_offsets_index = index;
+            }
+        }
+
+        self.height_offsets = height_offsets;
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -325,6 +912,11 @@ impl TerrainBlock {
     }
 }
 
+/// The largest value that can be stored for a block's offset height is u8::MAX
+/// because offset height is a u8. u8::MAX is then divided by 8 to get the
+/// normalized maximum.
+const MAX_NORMALIZED_OFFSET_HEIGHT: f32 = u8::MAX as f32 / 8.;
+
 #[inline]
 fn normalize(value: f32, min: f32, max: f32) -> f32 {
     (value - min) / (max - min)
@@ -376,6 +968,8 @@ bitflags! {
     }
 }
 
+crate::flags::impl_unknown_bits_set!(TrackControlPointFlags);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,6 +981,65 @@ mod tests {
         path::{Path, PathBuf},
     };
 
+    #[test]
+    fn test_serialized_field_names() {
+        let mut project_fields = vec![
+            "base_model_file_name",
+            "water_model_file_name",
+            "furniture_model_file_names",
+            "instances",
+            "terrain",
+            "attributes",
+            "excl",
+            "music_script_file_name",
+            "tracks",
+            "edit",
+        ];
+        project_fields.sort();
+        assert_eq!(
+            crate::serialized_field_names(&Project::default()),
+            project_fields
+        );
+
+        let mut instance_fields = vec![
+            "prev",
+            "next",
+            "selected",
+            "exclude_from_terrain",
+            "position",
+            "rotation",
+            "aabb_min",
+            "aabb_max",
+            "furniture_model_slot",
+            "model_id",
+            "attackable",
+            "toughness",
+            "wounds",
+            "unknown1",
+            "owner_unit_index",
+            "burnable",
+            "sfx_code",
+            "gfx_code",
+            "locked",
+            "exclude_from_terrain_shadow",
+            "exclude_from_walk",
+            "magic_item_id",
+            "particle_effect_code",
+            "furniture_dead_model_slot",
+            "dead_model_id",
+            "light",
+            "light_radius",
+            "light_ambient",
+            "unknown2",
+            "unknown3",
+        ];
+        instance_fields.sort();
+        assert_eq!(
+            crate::serialized_field_names(&Instance::default()),
+            instance_fields
+        );
+    }
+
     #[test]
     fn test_get_base_m3x_model_file_name() {
         let project = Project {
@@ -397,6 +1050,437 @@ mod tests {
         assert_eq!(project.get_base_m3x_model_file_name(), "base.M3X");
     }
 
+    #[test]
+    fn test_resolve_model_paths() {
+        let project = Project {
+            base_model_file_name: "base.M3D".to_string(),
+            water_model_file_name: Some("_7water.M3D".to_string()),
+            furniture_model_file_names: vec!["_4barrel.m3d".to_string()],
+            ..Default::default()
+        };
+
+        let dir = Path::new("GAMEDATA/1PBAT/B1_01");
+
+        assert_eq!(
+            project.resolve_model_paths(dir),
+            ResolvedModels {
+                base: dir.join("base.M3D"),
+                base_m3x: dir.join("base.M3X"),
+                water: Some(dir.join("_7water.M3D")),
+                water_m3x: Some(dir.join("_7water.M3X")),
+                furniture: vec![dir.join("_4barrel.m3d")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_instance_capabilities() {
+        let project = Project {
+            instances: vec![
+                Instance {
+                    attackable: 1,
+                    ..Default::default()
+                },
+                Instance {
+                    attackable: 1,
+                    burnable: 1,
+                    locked: 1,
+                    ..Default::default()
+                },
+                Instance {
+                    exclude_from_walk: 1,
+                    exclude_from_terrain: 1,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(project.attackable_instances().count(), 2);
+        assert_eq!(project.burnable_instances().count(), 1);
+        assert!(project.instances[1].is_locked());
+        assert!(!project.instances[0].is_locked());
+        assert!(project.instances[2].is_excluded_from_walk());
+        assert!(project.instances[2].is_excluded_from_terrain());
+        assert!(!project.instances[2].is_attackable());
+    }
+
+    #[test]
+    fn test_instance_transform() {
+        let instance = Instance {
+            position: DVec3::new(1., 2., 3.),
+            rotation: DVec3::new(0., 0.25, 0.),
+            ..Default::default()
+        };
+
+        let (translation, rotation, scale) = instance.transform();
+
+        assert_eq!(translation, Vec3::new(1., 2., 3.));
+        assert!((rotation * Vec3::X).abs_diff_eq(Vec3::NEG_Z, 1e-6));
+        assert_eq!(scale, Vec3::ONE);
+    }
+
+    #[test]
+    fn test_instance_world_aabb() {
+        let instance = Instance {
+            position: DVec3::new(10., 0., 20.),
+            rotation: DVec3::new(0., 0.25, 0.),
+            aabb_min: DVec3::new(8., 0., 18.),
+            aabb_max: DVec3::new(12., 5., 22.),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            instance.world_aabb(),
+            (Vec3::new(8., 0., 18.), Vec3::new(12., 5., 22.))
+        );
+    }
+
+    #[test]
+    fn test_instance_world_aabb_b1_01() {
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.PRJ");
+
+        let file = File::open(d).unwrap();
+        let p = Decoder::new(file).decode().unwrap();
+
+        // Every used instance is inside its own bounds on the ground plane,
+        // which would not be the case if the bounds were relative to the
+        // instance's position.
+        for instance in p.instances.iter().filter(|i| i.furniture_model_slot != 0) {
+            let (min, max) = instance.world_aabb();
+            let position = instance.position.as_vec3();
+
+            assert!(min.x <= max.x && min.y <= max.y && min.z <= max.z);
+            assert!(
+                (min.x..=max.x).contains(&position.x) && (min.z..=max.z).contains(&position.z),
+                "{:?} not in {:?}..{:?}",
+                position,
+                min,
+                max
+            );
+        }
+    }
+
+    #[test]
+    fn test_instance_become_dead() {
+        let project = Project {
+            furniture_model_file_names: vec!["_khut3.m3d".to_string(), "_khut3_d.m3d".to_string()],
+            ..Default::default()
+        };
+        let mut instance = Instance {
+            furniture_model_slot: 1,
+            model_id: 4,
+            furniture_dead_model_slot: 2,
+            dead_model_id: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            instance.furniture_model_file_name(&project),
+            Some("_khut3.m3d")
+        );
+        assert_eq!(
+            instance.dead_furniture_model_file_name(&project),
+            Some("_khut3_d.m3d")
+        );
+
+        instance.become_dead();
+
+        assert_eq!(
+            instance.furniture_model_file_name(&project),
+            Some("_khut3_d.m3d")
+        );
+        assert_eq!(instance.model_id, 5);
+        assert_eq!(instance.dead_furniture_model_file_name(&project), None);
+
+        // Already dead instances stay on their dead model.
+        instance.become_dead();
+        assert_eq!(
+            instance.furniture_model_file_name(&project),
+            Some("_khut3_d.m3d")
+        );
+    }
+
+    #[test]
+    fn test_instance_codes() {
+        let instance = Instance {
+            gfx_code: 3,
+            particle_effect_code: 1,
+            ..Default::default()
+        };
+
+        assert!(instance.has_animation());
+        assert!(instance.has_particle_effect());
+        assert!(!instance.has_sfx());
+        assert!(!instance.has_magic_item());
+    }
+
+    #[test]
+    fn test_terrain_slope_at_world_position() {
+        // A single 8x8 block where the height increases by 1 for every cell
+        // along the x-axis.
+        let terrain = Terrain {
+            width: 8,
+            height: 8,
+            heightmap1_blocks: vec![TerrainBlock {
+                base_height: 0,
+                height_offsets_index: 0,
+            }],
+            heightmap2_blocks: Vec::new(),
+            height_offsets: vec![(0..64).map(|i| (i % 8) * 8).collect()],
+        };
+
+        assert_eq!(
+            terrain.gradient_at_world_position(Heightmap::Furniture, 4., 4.),
+            Vec2::new(1., 0.)
+        );
+        // Edge cells use one-sided differences.
+        assert_eq!(
+            terrain.gradient_at_world_position(Heightmap::Furniture, 0., 0.),
+            Vec2::new(1., 0.)
+        );
+        assert_eq!(
+            terrain.gradient_at_world_position(Heightmap::Furniture, 7., 7.),
+            Vec2::new(1., 0.)
+        );
+        assert_eq!(
+            terrain.slope_at_world_position(Heightmap::Furniture, 4., 4.),
+            1.
+        );
+        assert_eq!(
+            terrain.normal_at_world_position(Heightmap::Furniture, 4., 4.),
+            Vec3::new(-1., 1., 0.).normalize()
+        );
+    }
+
+    #[test]
+    fn test_terrain_slope_at_world_position_empty() {
+        let terrain = Terrain::default();
+
+        assert_eq!(
+            terrain.gradient_at_world_position(Heightmap::Furniture, 4., 4.),
+            Vec2::ZERO
+        );
+        assert_eq!(
+            terrain.normal_at_world_position(Heightmap::Furniture, 4., 4.),
+            Vec3::Y
+        );
+    }
+
+    #[test]
+    fn test_terrain_to_heightfield() {
+        // Two 8x8 blocks side by side with a 12x3 terrain, so the second block
+        // is only partly used.
+        let terrain = Terrain {
+            width: 12,
+            height: 3,
+            heightmap1_blocks: vec![
+                TerrainBlock {
+                    base_height: 0,
+                    height_offsets_index: 0,
+                },
+                TerrainBlock {
+                    base_height: 2048,
+                    height_offsets_index: 0,
+                },
+            ],
+            heightmap2_blocks: Vec::new(),
+            height_offsets: vec![(0..64).map(|i| i * 2).collect()],
+        };
+
+        let (heights, size, cell_size) = terrain.to_heightfield(Heightmap::Furniture);
+
+        assert_eq!(size, UVec2::new(12, 3));
+        assert_eq!(cell_size, Vec2::ONE);
+        assert_eq!(heights.len(), 36);
+        for y in 0..3 {
+            for x in 0..12 {
+                assert_eq!(
+                    heights[(x + y * 12) as usize],
+                    terrain.height_at_world_position(Heightmap::Furniture, x as f32, y as f32)
+                );
+            }
+        }
+        assert_eq!(heights[1], 0.25);
+        assert_eq!(heights[8], 2.);
+    }
+
+    #[test]
+    fn test_project_sync_dimensions() {
+        let block = || TerrainBlock {
+            base_height: 0,
+            height_offsets_index: 0,
+        };
+
+        let mut project = Project {
+            terrain: Terrain {
+                width: 16,
+                height: 9,
+                heightmap1_blocks: vec![block(), block(), block(), block()],
+                heightmap2_blocks: vec![block(), block(), block(), block()],
+                height_offsets: vec![vec![0; 64]],
+            },
+            ..Default::default()
+        };
+
+        project.sync_dimensions().unwrap();
+
+        assert_eq!(project.attributes.width, 16);
+        assert_eq!(project.attributes.height, 9);
+
+        project.terrain.height = 17;
+
+        assert_eq!(
+            project.sync_dimensions(),
+            Err(DimensionsError {
+                expected_block_count: 6,
+                heightmap1_block_count: 4,
+                heightmap2_block_count: 4,
+            })
+        );
+        assert_eq!(project.attributes.height, 9);
+    }
+
+    #[test]
+    fn test_terrain_set_height_offset_at() {
+        let block = |height_offsets_index| TerrainBlock {
+            base_height: 0,
+            height_offsets_index,
+        };
+
+        let mut terrain = Terrain {
+            width: 16,
+            height: 8,
+            heightmap1_blocks: vec![block(0), block(0)],
+            heightmap2_blocks: vec![block(0), block(1)],
+            height_offsets: vec![vec![0; 64], vec![0; 64]],
+        };
+
+        // The height offsets of the block are shared, so they are copied.
+        terrain.set_height_offset_at(Heightmap::Furniture, 9, 1, 8);
+
+        assert_eq!(terrain.height_offsets.len(), 3);
+        assert_eq!(terrain.heightmap1_blocks[0].height_offsets_index, 0);
+        assert_eq!(terrain.heightmap1_blocks[1].height_offsets_index, 2);
+        assert_eq!(terrain.height_offsets[0], vec![0; 64]);
+        assert_eq!(terrain.height_offsets[2][9], 8);
+        assert_eq!(
+            terrain.height_at_world_position(Heightmap::Furniture, 9., 1.),
+            1.
+        );
+        assert_eq!(
+            terrain.height_at_world_position(Heightmap::Base, 1., 1.),
+            0.
+        );
+
+        // The height offsets of the block are not shared, so they are changed
+        // in place.
+        terrain.set_height_offset_at(Heightmap::Base, 8, 0, 16);
+
+        assert_eq!(terrain.height_offsets.len(), 3);
+        assert_eq!(terrain.height_offsets[1][0], 16);
+    }
+
+    #[test]
+    fn test_terrain_apply_heightmap_image() {
+        let block = |base_height, height_offsets_index| TerrainBlock {
+            base_height,
+            height_offsets_index,
+        };
+
+        let mut terrain = Terrain {
+            width: 16,
+            height: 8,
+            heightmap1_blocks: vec![block(0, 0), block(0, 0)],
+            heightmap2_blocks: vec![block(0, 0), block(4096, 1)],
+            height_offsets: vec![vec![0; 64], (0..64).map(|i| i * 2).collect()],
+        };
+        let original = terrain.clone();
+
+        let img = terrain.base_heightmap_image();
+        terrain
+            .apply_heightmap_image(Heightmap::Base, &img)
+            .unwrap();
+
+        for y in 0..8 {
+            for x in 0..16 {
+                let expected =
+                    original.height_at_world_position(Heightmap::Base, x as f32, y as f32);
+                let actual = terrain.height_at_world_position(Heightmap::Base, x as f32, y as f32);
+                assert!(
+                    (expected - actual).abs() <= 0.25,
+                    "height at ({x}, {y}) should be close to {expected}, got {actual}"
+                );
+            }
+        }
+
+        // The other heightmap is unchanged.
+        for y in 0..8 {
+            for x in 0..16 {
+                assert_eq!(
+                    terrain.height_at_world_position(Heightmap::Furniture, x as f32, y as f32),
+                    0.
+                );
+            }
+        }
+
+        let img = DynamicImage::new_luma8(8, 8);
+        assert_eq!(
+            terrain.apply_heightmap_image(Heightmap::Base, &img),
+            Err(HeightmapImageError {
+                expected_width: 16,
+                expected_height: 8,
+                width: 8,
+                height: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_terrain_rebuild_height_offsets() {
+        let block = |height_offsets_index| TerrainBlock {
+            base_height: 0,
+            height_offsets_index,
+        };
+
+        let mut terrain = Terrain {
+            width: 16,
+            height: 8,
+            heightmap1_blocks: vec![block(0), block(2)],
+            heightmap2_blocks: vec![block(3), block(0)],
+            height_offsets: vec![vec![1; 64], vec![2; 64], vec![3; 64], vec![1; 64]],
+        };
+
+        terrain.rebuild_height_offsets();
+
+        assert_eq!(terrain.height_offsets, vec![vec![1; 64], vec![3; 64]]);
+        let indices = |blocks: &[TerrainBlock]| {
+            blocks
+                .iter()
+                .map(|b| b.height_offsets_index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(indices(&terrain.heightmap1_blocks), vec![0, 1]);
+        assert_eq!(indices(&terrain.heightmap2_blocks), vec![0, 0]);
+
+        // Rebuilding again is a no-op.
+        let before = terrain.clone();
+        terrain.rebuild_height_offsets();
+        assert_eq!(terrain.height_offsets, before.height_offsets);
+        assert_eq!(
+            indices(&terrain.heightmap1_blocks),
+            indices(&before.heightmap1_blocks)
+        );
+        assert_eq!(
+            indices(&terrain.heightmap2_blocks),
+            indices(&before.heightmap2_blocks)
+        );
+    }
+
     #[test]
     fn test_get_water_m3x_model_file_name() {
         let project = Project {
@@ -443,16 +1527,11 @@ mod tests {
 
     #[test]
     fn test_decode_b1_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B1_01",
-            "B1_01.PRJ",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.PRJ");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -506,18 +1585,32 @@ mod tests {
         roundtrip_test(&original_bytes, &p);
     }
 
+    #[test]
+    fn test_json_roundtrip_b1_01() {
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.PRJ");
+
+        let original_bytes = std::fs::read(d.clone()).unwrap();
+
+        let file = File::open(d).unwrap();
+        let p = Decoder::new(file).decode().unwrap();
+
+        let json = serde_json::to_string(&p).unwrap();
+        let p: Project = serde_json::from_str(&json).unwrap();
+
+        roundtrip_test(&original_bytes, &p);
+    }
+
     #[test]
     fn test_decode_b2_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B2_01",
-            "B2_01.PRJ",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B2_01")
+            .join("B2_01.PRJ");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -534,16 +1627,11 @@ mod tests {
 
     #[test]
     fn test_decode_mb4_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B4_01",
-            "MB4_01.PRJ",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B4_01")
+            .join("MB4_01.PRJ");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -560,16 +1648,11 @@ mod tests {
 
     #[test]
     fn test_decode_b4_09() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B4_09",
-            "B4_09.PRJ",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B4_09")
+            .join("B4_09.PRJ");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -583,16 +1666,11 @@ mod tests {
 
     #[test]
     fn test_decode_b5_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B5_01",
-            "B5_01.PRJ",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B5_01")
+            .join("B5_01.PRJ");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -604,14 +1682,7 @@ mod tests {
 
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path().unwrap().join("1PBAT");
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "projects"]
             .iter()
@@ -674,7 +1745,7 @@ mod tests {
             // model.
             for instance in &project.instances {
                 assert!(
-                    instance.gfx_code == 0 || instance.furniture_model_slot != 0,
+                    !instance.has_animation() || instance.furniture_model_slot != 0,
                     "instance with GFX code {} has no furniture model slot",
                     instance.gfx_code
                 );
@@ -722,7 +1793,8 @@ mod tests {
                 let output_path =
                     append_ext("ron", root_output_dir.join(path.file_name().unwrap()));
                 let mut output_file = File::create(output_path).unwrap();
-                ron::ser::to_writer_pretty(&mut output_file, &project, Default::default()).unwrap();
+                ron::ser::to_writer_pretty(&mut output_file, &project, crate::ron_config())
+                    .unwrap();
 
                 // Furniture and base heightmap images.
                 let output_dir = root_output_dir.join("heightmaps");
@@ -0,0 +1,195 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    light::{self, Light},
+    m3d::{self, M3d},
+    shadow::{self, Lightmap},
+};
+
+use super::*;
+
+#[derive(Debug)]
+pub enum SceneError {
+    IoError(IoError),
+    InvalidPath(PathBuf),
+    ProjectDecodeError(DecodeError),
+    LightDecodeError(light::DecodeError),
+    LightmapDecodeError(shadow::DecodeError),
+    ModelDecodeError(PathBuf, m3d::DecodeError),
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<IoError> for SceneError {
+    fn from(error: IoError) -> Self {
+        SceneError::IoError(error)
+    }
+}
+
+impl From<DecodeError> for SceneError {
+    fn from(error: DecodeError) -> Self {
+        SceneError::ProjectDecodeError(error)
+    }
+}
+
+impl From<light::DecodeError> for SceneError {
+    fn from(error: light::DecodeError) -> Self {
+        SceneError::LightDecodeError(error)
+    }
+}
+
+impl From<shadow::DecodeError> for SceneError {
+    fn from(error: shadow::DecodeError) -> Self {
+        SceneError::LightmapDecodeError(error)
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::IoError(e) => write!(f, "IO error: {}", e),
+            SceneError::InvalidPath(path) => write!(f, "invalid path: {}", path.display()),
+            SceneError::ProjectDecodeError(e) => write!(f, "could not decode project: {}", e),
+            SceneError::LightDecodeError(e) => write!(f, "could not decode lights: {}", e),
+            SceneError::LightmapDecodeError(e) => write!(f, "could not decode lightmap: {}", e),
+            SceneError::ModelDecodeError(path, e) => {
+                write!(f, "could not decode model {}: {}", path.display(), e)
+            }
+        }
+    }
+}
+
+/// A project together with everything it needs to be rendered: its lights, its
+/// lightmap and its models.
+#[derive(Clone, Debug)]
+pub struct Scene {
+    /// The ID of the project, e.g. `B1_01`. This is the file stem of the
+    /// project file.
+    pub id: String,
+    pub project: Project,
+    /// The lights from the project's `.LIT` file.
+    pub lights: Vec<Light>,
+    /// The lightmap from the project's `.SHD` file.
+    pub lightmap: Lightmap,
+    /// The base model. This is always the chunked M3X version.
+    pub base_model: M3d,
+    /// The water model, if any. This is always the chunked M3X version.
+    pub water_model: Option<M3d>,
+    /// The furniture models, in the same order as
+    /// [`Project::furniture_model_file_names`].
+    pub furniture_models: Vec<M3d>,
+}
+
+impl Scene {
+    /// Loads the project at `prj_path` and the lights, lightmap and models it
+    /// references.
+    ///
+    /// The `.LIT` and `.SHD` files are expected to be next to the project file
+    /// and have the same file stem, e.g. `B1_01/B1_01.LIT` for
+    /// `B1_01/B1_01.PRJ`. Files
+    /// are looked up case-insensitively because the game files are not
+    /// consistent in their casing.
+    pub fn load(prj_path: &Path) -> Result<Scene, SceneError> {
+        let parent_path = prj_path
+            .parent()
+            .ok_or_else(|| SceneError::InvalidPath(prj_path.to_path_buf()))?;
+        let id = prj_path
+            .file_stem()
+            .ok_or_else(|| SceneError::InvalidPath(prj_path.to_path_buf()))?
+            .to_string_lossy()
+            .to_string();
+
+        let project = Decoder::new(File::open(prj_path)?).decode()?;
+
+        let lights =
+            light::Decoder::new(open_file(parent_path, &format!("{}.LIT", id))?).decode()?;
+        let lightmap =
+            shadow::Decoder::new(open_file(parent_path, &format!("{}.SHD", id))?).decode()?;
+
+        let model_paths = project.resolve_model_paths(parent_path);
+
+        let base_model = load_model(&model_paths.base_m3x)?;
+        let water_model = model_paths
+            .water_m3x
+            .as_deref()
+            .map(load_model)
+            .transpose()?;
+        let furniture_models = model_paths
+            .furniture
+            .iter()
+            .map(|path| load_model(path))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Scene {
+            id,
+            project,
+            lights,
+            lightmap,
+            base_model,
+            water_model,
+            furniture_models,
+        })
+    }
+}
+
+fn load_model(path: &Path) -> Result<M3d, SceneError> {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Err(SceneError::InvalidPath(path.to_path_buf()));
+    };
+
+    m3d::Decoder::new(open_file(dir, &file_name.to_string_lossy())?)
+        .decode()
+        .map_err(|e| SceneError::ModelDecodeError(path.to_path_buf(), e))
+}
+
+/// Opens the file named `file_name` in `dir`, ignoring case.
+fn open_file(dir: &Path, file_name: &str) -> Result<File, SceneError> {
+    let path = dir.join(file_name);
+    if path.is_file() {
+        return Ok(File::open(path)?);
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry
+            .file_name()
+            .to_string_lossy()
+            .eq_ignore_ascii_case(file_name)
+        {
+            return Ok(File::open(entry.path())?);
+        }
+    }
+
+    Err(IoError::new(
+        ErrorKind::NotFound,
+        format!("file not found: {}", path.display()),
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_b1_01() {
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.PRJ");
+
+        let scene = Scene::load(&d).unwrap();
+
+        assert_eq!(scene.id, "B1_01");
+        assert!(!scene.lights.is_empty());
+        assert!(!scene.base_model.objects.is_empty());
+        assert!(scene.water_model.is_some());
+        assert_eq!(scene.furniture_models.len(), 10);
+    }
+}
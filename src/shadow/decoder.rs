@@ -1,7 +1,7 @@
 use super::*;
 use std::{
     fmt,
-    io::{Error as IoError, Read, Seek},
+    io::{Error as IoError, Read},
     mem::size_of,
 };
 
@@ -47,14 +47,15 @@ impl fmt::Display for DecodeError {
     }
 }
 
+/// Decodes a lightmap from a `.SHD` file.
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
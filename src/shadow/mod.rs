@@ -4,12 +4,12 @@ mod encoder;
 #[cfg(feature = "bevy_reflect")]
 use bevy_reflect::prelude::*;
 use image::{DynamicImage, GenericImage, Rgba};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 pub use decoder::{DecodeError, Decoder};
 pub use encoder::{EncodeError, Encoder};
 
-#[derive(Clone, Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Lightmap {
     pub width: u32,
@@ -25,6 +25,11 @@ pub struct Lightmap {
 }
 
 impl Lightmap {
+    /// Decodes a lightmap from the bytes of a `.SHD` file.
+    pub fn from_slice(bytes: &[u8]) -> Result<Lightmap, DecodeError> {
+        Decoder::new(bytes).decode()
+    }
+
     fn normalized_offset_height(offset_height: u8) -> f32 {
         offset_height as f32 / 8.0
     }
@@ -134,7 +139,7 @@ impl Lightmap {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 
 pub struct LightmapBlock {
@@ -257,16 +262,11 @@ mod tests {
 
     #[test]
     fn test_decode_b1_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B1_01",
-            "B1_01.SHD",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.SHD");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -281,18 +281,32 @@ mod tests {
         roundtrip_test(&original_bytes, &lightmap);
     }
 
+    #[test]
+    fn test_json_roundtrip_b1_01() {
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B1_01")
+            .join("B1_01.SHD");
+
+        let original_bytes = std::fs::read(d.clone()).unwrap();
+
+        let file = File::open(d).unwrap();
+        let lightmap = Decoder::new(file).decode().unwrap();
+
+        let json = serde_json::to_string(&lightmap).unwrap();
+        let lightmap: Lightmap = serde_json::from_str(&json).unwrap();
+
+        roundtrip_test(&original_bytes, &lightmap);
+    }
+
     #[test]
     fn test_decode_mb4_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B4_01",
-            "MB4_01.SHD",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B4_01")
+            .join("MB4_01.SHD");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -304,16 +318,11 @@ mod tests {
 
     #[test]
     fn test_decode_b4_09() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B4_09",
-            "B4_09.SHD",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B4_09")
+            .join("B4_09.SHD");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -325,16 +334,11 @@ mod tests {
 
     #[test]
     fn test_decode_b5_01() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-            "B5_01",
-            "B5_01.SHD",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path()
+            .unwrap()
+            .join("1PBAT")
+            .join("B5_01")
+            .join("B5_01.SHD");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -346,14 +350,7 @@ mod tests {
 
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "GAMEDATA",
-            "1PBAT",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::gamedata_path().unwrap().join("1PBAT");
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "shadows"]
             .iter()
@@ -418,7 +415,7 @@ mod tests {
                 let output_path =
                     append_ext("ron", root_output_dir.join(path.file_name().unwrap()));
                 let mut output_file = File::create(output_path).unwrap();
-                ron::ser::to_writer_pretty(&mut output_file, &lightmap, Default::default())
+                ron::ser::to_writer_pretty(&mut output_file, &lightmap, crate::ron_config())
                     .unwrap();
 
                 // Image.
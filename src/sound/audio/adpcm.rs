@@ -19,6 +19,23 @@ const STEP_TABLE: [i16; 89] = [
     15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
 ];
 
+/// The largest valid index into the step size table.
+pub(crate) const MAX_STEP_INDEX: i16 = STEP_TABLE.len() as i16 - 1;
+
+/// The index that marks the end of the ADPCM blocks in a `.MAD` or `.SAD`
+/// file. What follows is raw PCM data.
+pub(crate) const END_OF_BLOCKS_INDEX: i16 = 99;
+
+/// Returns `true` if `index` is a plausible index in the header of an ADPCM
+/// block, i.e. it is either a valid step index or the end of blocks marker.
+///
+/// Files with no magic number, like `.MAD` and `.SAD` files, are checked with
+/// this so that the wrong kind of file, e.g. one with big-endian data, fails
+/// with an error instead of decoding to noise.
+pub(crate) fn is_valid_block_index(index: i16) -> bool {
+    (0..=MAX_STEP_INDEX).contains(&index) || index == END_OF_BLOCKS_INDEX
+}
+
 #[derive(Clone, Debug)]
 pub struct AdpcmBlock {
     pub sample: i16,
@@ -1,14 +1,19 @@
 use super::*;
-use crate::sound::audio::{adpcm::AdpcmBlock, pcm::Pcm16Block, BlockError};
+use crate::sound::audio::{
+    adpcm::{self, AdpcmBlock},
+    pcm::Pcm16Block,
+    BlockError,
+};
 use std::{
     fmt,
-    io::{self, Read, Seek},
+    io::{self, Read},
 };
 
 #[derive(Debug)]
 pub enum DecodeError {
     IoError(io::Error),
     BlockError(BlockError),
+    InvalidBlockIndex(i16),
 }
 
 impl std::error::Error for DecodeError {}
@@ -30,18 +35,22 @@ impl fmt::Display for DecodeError {
         match self {
             DecodeError::IoError(e) => write!(f, "IO error: {}", e),
             DecodeError::BlockError(e) => write!(f, "block error: {}", e),
+            DecodeError::InvalidBlockIndex(index) => {
+                write!(f, "invalid ADPCM block index: {}", index)
+            }
         }
     }
 }
 
+/// Decodes mono audio from a `.MAD` file.
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
@@ -77,7 +86,11 @@ impl<R: Read + Seek> Decoder<R> {
             let sample = i16::from_le_bytes([buf[0], buf[1]]);
             let index = i16::from_le_bytes([buf[2], buf[3]]);
 
-            if index == 99 {
+            if !adpcm::is_valid_block_index(index) {
+                return Err(DecodeError::InvalidBlockIndex(index));
+            }
+
+            if index == adpcm::END_OF_BLOCKS_INDEX {
                 sample99 = sample;
                 index99 = index;
                 break;
@@ -107,17 +107,24 @@ mod tests {
         assert_eq!(original_bytes, encoded_bytes);
     }
 
+    #[test]
+    fn test_decode_invalid_block_index() {
+        // A block header with the sample and index stored big-endian.
+        let bytes = [0x00, 0x10, 0x00, 0x20];
+
+        assert!(matches!(
+            Decoder::new(&bytes[..]).decode(),
+            Err(DecodeError::InvalidBlockIndex(0x2000))
+        ));
+    }
+
     #[test]
     fn test_decode_a_ayesir() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "SOUND",
-            "SP_ENG",
-            "A_AYESIR.MAD",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path()
+            .unwrap()
+            .join("SOUND")
+            .join("SP_ENG")
+            .join("A_AYESIR.MAD");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -143,9 +150,7 @@ mod tests {
 
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [std::env::var("DARKOMEN_PATH").unwrap().as_str(), "DARKOMEN"]
-            .iter()
-            .collect();
+        let d: PathBuf = crate::game_path().unwrap();
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "mads"]
             .iter()
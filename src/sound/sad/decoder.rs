@@ -1,13 +1,17 @@
 use super::*;
-use crate::sound::audio::{adpcm::AdpcmBlock, pcm::Pcm16Block};
+use crate::sound::audio::{
+    adpcm::{self, AdpcmBlock},
+    pcm::Pcm16Block,
+};
 use std::{
     fmt,
-    io::{self, Read, Seek},
+    io::{self, Read},
 };
 
 #[derive(Debug)]
 pub enum DecodeError {
     IoError(io::Error),
+    InvalidBlockIndex(i16),
 }
 
 impl std::error::Error for DecodeError {}
@@ -22,18 +26,22 @@ impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DecodeError::IoError(e) => write!(f, "IO error: {}", e),
+            DecodeError::InvalidBlockIndex(index) => {
+                write!(f, "invalid ADPCM block index: {}", index)
+            }
         }
     }
 }
 
+/// Decodes stereo audio from a `.SAD` file.
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
@@ -65,7 +73,14 @@ impl<R: Read + Seek> Decoder<R> {
             let right_sample = i16::from_le_bytes([buf[4], buf[5]]);
             let right_index = i16::from_le_bytes([buf[6], buf[7]]);
 
-            if left_index == 99 && right_index == 99 {
+            for index in [left_index, right_index] {
+                if !adpcm::is_valid_block_index(index) {
+                    return Err(DecodeError::InvalidBlockIndex(index));
+                }
+            }
+
+            if left_index == adpcm::END_OF_BLOCKS_INDEX && right_index == adpcm::END_OF_BLOCKS_INDEX
+            {
                 left_sample99 = left_sample;
                 left_index99 = left_index;
                 right_sample99 = right_sample;
@@ -114,15 +114,11 @@ mod tests {
 
     #[test]
     fn test_decode_09eerie() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "SOUND",
-            "MUSIC",
-            "09EERIE.SAD",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path()
+            .unwrap()
+            .join("SOUND")
+            .join("MUSIC")
+            .join("09EERIE.SAD");
 
         let original_bytes = std::fs::read(d.clone()).unwrap();
 
@@ -148,9 +144,7 @@ mod tests {
 
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [std::env::var("DARKOMEN_PATH").unwrap().as_str(), "DARKOMEN"]
-            .iter()
-            .collect();
+        let d: PathBuf = crate::game_path().unwrap();
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "sads"]
             .iter()
@@ -5,7 +5,7 @@ use super::{lexer::*, *};
 use indexmap::IndexMap;
 use std::{
     fmt,
-    io::{Error as IoError, Read, Seek},
+    io::{Error as IoError, Read},
 };
 
 #[derive(Debug)]
@@ -317,15 +317,16 @@ impl fmt::Display for DecodeError {
     }
 }
 
+/// Decodes a music script from a `.FSM` file.
 #[derive(Debug)]
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
@@ -354,15 +355,11 @@ mod tests {
 
     #[test]
     fn test_decode() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "SOUND",
-            "SCRIPT",
-            "EERIE9.FSM",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path()
+            .unwrap()
+            .join("SOUND")
+            .join("SCRIPT")
+            .join("EERIE9.FSM");
 
         let script = Decoder::new(fs::File::open(d).unwrap()).decode().unwrap();
 
@@ -449,15 +446,7 @@ mod tests {
                 use std::fs;
 
                 let file = $value;
-                let d: PathBuf = [
-                    std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-                    "DARKOMEN",
-                    "SOUND",
-                    "SCRIPT",
-                    file,
-                ]
-                    .iter()
-                    .collect();
+                let d: PathBuf = crate::game_path().unwrap().join("SOUND").join("SCRIPT").join(file);
 
                 let original = fs::read_to_string(d.clone()).unwrap();
                 let script = Decoder::new(fs::File::open(d.clone()).unwrap())
@@ -525,7 +514,7 @@ mod tests {
 
                 let output_path = append_ext("ron", output_path);
                 let mut output_file = File::create(output_path).unwrap();
-                ron::ser::to_writer_pretty(&mut output_file, &script, Default::default())
+                ron::ser::to_writer_pretty(&mut output_file, &script, crate::ron_config())
                     .unwrap();
             }
         )*
@@ -541,9 +530,7 @@ mod tests {
 
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [std::env::var("DARKOMEN_PATH").unwrap().as_str(), "DARKOMEN"]
-            .iter()
-            .collect();
+        let d: PathBuf = crate::game_path().unwrap();
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "sound"]
             .iter()
@@ -600,7 +587,7 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &script, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &script, crate::ron_config()).unwrap();
         });
     }
 
@@ -5,13 +5,13 @@ mod lexer;
 #[cfg(feature = "bevy_reflect")]
 use bevy_reflect::prelude::*;
 use indexmap::IndexMap;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::ops::Index;
 
 pub use decoder::{DecodeError, Decoder};
 pub use encoder::{EncodeError, Encoder};
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Script {
     /// A map of state IDs to number. The purpose of the number is unknown and
@@ -41,7 +41,7 @@ pub struct Script {
     pub patterns: IndexMap<PatternId, Pattern>,
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Pattern {
     /// A list of sequences to choose from when playing the pattern. Patterns
@@ -63,7 +63,7 @@ pub fn default_state_id() -> StateId {
     "default".to_string()
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct PatternId(String);
 
@@ -90,7 +90,7 @@ pub fn end_pattern_id() -> PatternId {
 pub type SampleId = String;
 
 /// A sequence of samples to play in order.
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Sequence(pub(crate) Vec<SampleId>);
 
@@ -125,7 +125,7 @@ impl Sequence {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct StateTable(pub(crate) IndexMap<StateId, PatternId>);
 
 impl StateTable {
@@ -2,7 +2,7 @@ use super::{lexer::*, *};
 use std::{
     collections::HashMap,
     fmt,
-    io::{Error as IoError, Read, Seek},
+    io::{Error as IoError, Read},
 };
 
 struct IdentifierToken {
@@ -139,7 +139,7 @@ fn parse_sound(tokens: &[Token]) -> (Sound, usize) {
     (sound, i)
 }
 
-fn parse_sfx(tokens: &[Token]) -> (Sfx, usize) {
+fn parse_sfx(tokens: &[Token]) -> Result<(Sfx, usize), DecodeError> {
     let mut sfx = Sfx::default();
 
     assert!(tokens.len() >= 2);
@@ -201,14 +201,32 @@ fn parse_sfx(tokens: &[Token]) -> (Sfx, usize) {
                             .expect("priority should be an unsigned 8-bit integer");
                     }
                     "TYPE" => {
-                        let value: u8 = value.parse().expect("type should be an 8-bit integer");
-                        sfx.typ = SfxType::try_from(value).expect("type should be valid");
+                        let value: u8 = value.parse().map_err(|_| DecodeError::InvalidInteger {
+                            field: "SFX type",
+                            value: value.clone(),
+                            line: value_token.line,
+                            column: value_token.column,
+                        })?;
+                        sfx.typ =
+                            SfxType::try_from(value).map_err(|_| DecodeError::InvalidSfxType {
+                                value,
+                                line: value_token.line,
+                                column: value_token.column,
+                            })?;
                     }
                     "FLAGS" => {
-                        sfx.flags = SfxFlags::from_bits(
-                            value.parse().expect("flags should be an 8-bit integer"),
-                        )
-                        .expect("flags should be valid");
+                        let value: u8 = value.parse().map_err(|_| DecodeError::InvalidInteger {
+                            field: "SFX flags",
+                            value: value.clone(),
+                            line: value_token.line,
+                            column: value_token.column,
+                        })?;
+                        sfx.flags =
+                            SfxFlags::from_bits(value).ok_or(DecodeError::InvalidSfxFlags {
+                                value,
+                                line: value_token.line,
+                                column: value_token.column,
+                            })?;
                     }
                     "SNDS" => {
                         sfx.sounds = Vec::with_capacity(
@@ -241,10 +259,10 @@ fn parse_sfx(tokens: &[Token]) -> (Sfx, usize) {
         }
     }
 
-    (sfx, i)
+    Ok((sfx, i))
 }
 
-fn parse_packet(tokens: &[Token]) -> (HashMap<u8, Sfx>, usize) {
+fn parse_packet(tokens: &[Token]) -> Result<(HashMap<u8, Sfx>, usize), DecodeError> {
     let mut sfxs = HashMap::new();
 
     let mut i = 0;
@@ -257,7 +275,7 @@ fn parse_packet(tokens: &[Token]) -> (HashMap<u8, Sfx>, usize) {
             Token::Define { .. } => {
                 i += 1; // consume this token
 
-                let (sfx, pos) = parse_sfx(&tokens[i..]);
+                let (sfx, pos) = parse_sfx(&tokens[i..])?;
                 sfxs.insert(sfx.id, sfx);
                 i += pos; // consume the tokens the SFX parsing consumed
             }
@@ -271,10 +289,10 @@ fn parse_packet(tokens: &[Token]) -> (HashMap<u8, Sfx>, usize) {
         }
     }
 
-    (sfxs, i)
+    Ok((sfxs, i))
 }
 
-fn parse(tokens: &[Token]) -> Packet {
+fn parse(tokens: &[Token]) -> Result<Packet, DecodeError> {
     let mut packet = Packet::default();
 
     let mut i = 0;
@@ -301,7 +319,7 @@ fn parse(tokens: &[Token]) -> Packet {
                 match field_token.value.as_str() {
                     "PACKET" => {
                         packet.name = value.to_string();
-                        let (sfxs, pos) = parse_packet(&tokens[i..]);
+                        let (sfxs, pos) = parse_packet(&tokens[i..])?;
                         packet.sfxs = sfxs;
                         i += pos; // consume the tokens the packet parsing consumed
                     }
@@ -318,12 +336,32 @@ fn parse(tokens: &[Token]) -> Packet {
         }
     }
 
-    packet
+    Ok(packet)
 }
 
 #[derive(Debug)]
 pub enum DecodeError {
     IoError(IoError),
+    /// The SFX type is not one of the known types.
+    InvalidSfxType {
+        value: u8,
+        line: usize,
+        column: usize,
+    },
+    /// The SFX flags contain bits that are not known flags.
+    InvalidSfxFlags {
+        value: u8,
+        line: usize,
+        column: usize,
+    },
+    /// A value is not an integer or does not fit in the integer type of its
+    /// field, e.g. an SFX type of `300`.
+    InvalidInteger {
+        field: &'static str,
+        value: String,
+        line: usize,
+        column: usize,
+    },
 }
 
 impl std::error::Error for DecodeError {}
@@ -338,19 +376,36 @@ impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DecodeError::IoError(e) => write!(f, "IO error: {}", e),
+            DecodeError::InvalidSfxType {
+                value,
+                line,
+                column,
+            } => write!(f, "{}:{}: invalid SFX type: {}", line, column, value),
+            DecodeError::InvalidSfxFlags {
+                value,
+                line,
+                column,
+            } => write!(f, "{}:{}: invalid SFX flags: {}", line, column, value),
+            DecodeError::InvalidInteger {
+                field,
+                value,
+                line,
+                column,
+            } => write!(f, "{}:{}: invalid {}: {}", line, column, field, value),
         }
     }
 }
 
+/// Decodes a sound effects packet from an `.H` file.
 #[derive(Debug)]
 pub struct Decoder<R>
 where
-    R: Read + Seek,
+    R: Read,
 {
     reader: R,
 }
 
-impl<R: Read + Seek> Decoder<R> {
+impl<R: Read> Decoder<R> {
     pub fn new(reader: R) -> Self {
         Decoder { reader }
     }
@@ -361,7 +416,7 @@ impl<R: Read + Seek> Decoder<R> {
 
         let tokens = lex(buffer.as_str());
 
-        let script = parse(&tokens);
+        let script = parse(&tokens)?;
 
         Ok(script)
     }
@@ -379,15 +434,11 @@ mod tests {
 
     #[test]
     fn test_decode() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "SOUND",
-            "H",
-            "WATAFALL.H",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path()
+            .unwrap()
+            .join("SOUND")
+            .join("H")
+            .join("WATAFALL.H");
 
         let packet = Decoder::new(fs::File::open(d).unwrap()).decode().unwrap();
 
@@ -539,15 +590,71 @@ mod tests {
         "10:9: unexpected sound directive, found 'FOO' with value 'bar'"
     );
 
+    #[test]
+    fn test_decode_invalid_sfx_type() {
+        let file_as_string = r#"//# PACKET: Foo
+#define SFX_FOO           		 0
+//# TYPE: 7"#;
+
+        let result =
+            Decoder::new(std::io::Cursor::new(file_as_string.as_bytes().to_vec())).decode();
+
+        assert!(matches!(
+            result,
+            Err(DecodeError::InvalidSfxType {
+                value: 7,
+                line: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_invalid_sfx_flags() {
+        let file_as_string = r#"//# PACKET: Foo
+#define SFX_FOO           		 0
+//# FLAGS: 4"#;
+
+        let result =
+            Decoder::new(std::io::Cursor::new(file_as_string.as_bytes().to_vec())).decode();
+
+        assert!(matches!(
+            result,
+            Err(DecodeError::InvalidSfxFlags {
+                value: 4,
+                line: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_out_of_range_sfx_type() {
+        let file_as_string = r#"//# PACKET: Foo
+#define SFX_FOO           		 0
+//# TYPE: 300"#;
+
+        let result =
+            Decoder::new(std::io::Cursor::new(file_as_string.as_bytes().to_vec())).decode();
+
+        assert!(
+            matches!(
+                &result,
+                Err(DecodeError::InvalidInteger {
+                    field: "SFX type",
+                    value,
+                    line: 3,
+                    ..
+                }) if value == "300"
+            ),
+            "{:?}",
+            result
+        );
+    }
+
     #[test]
     fn test_decode_all() {
-        let d: PathBuf = [
-            std::env::var("DARKOMEN_PATH").unwrap().as_str(),
-            "DARKOMEN",
-            "SOUND",
-        ]
-        .iter()
-        .collect();
+        let d: PathBuf = crate::game_path().unwrap().join("SOUND");
 
         let root_output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "decoded", "sound"]
             .iter()
@@ -605,7 +712,7 @@ mod tests {
 
             let output_path = append_ext("ron", output_dir.join(path.file_name().unwrap()));
             let mut output_file = File::create(output_path).unwrap();
-            ron::ser::to_writer_pretty(&mut output_file, &packet, Default::default()).unwrap();
+            ron::ser::to_writer_pretty(&mut output_file, &packet, crate::ron_config()).unwrap();
         });
     }
 
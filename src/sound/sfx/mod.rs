@@ -10,7 +10,7 @@ use std::collections::HashMap;
 
 pub use decoder::{DecodeError, Decoder};
 
-#[derive(Clone, Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Packet {
     /// The name of the packet, e.g. `WaterFallingTears`.
@@ -19,7 +19,23 @@ pub struct Packet {
     pub sfxs: HashMap<SfxId, Sfx>,
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+impl Packet {
+    /// Returns the total number of sounds across all SFX in the packet.
+    pub fn total_sound_count(&self) -> usize {
+        self.sfxs.values().map(|sfx| sfx.sounds.len()).sum()
+    }
+
+    /// Returns the number of SFX in the packet for each SFX type.
+    pub fn sfx_count_by_type(&self) -> HashMap<SfxType, usize> {
+        let mut counts = HashMap::new();
+        for sfx in self.sfxs.values() {
+            *counts.entry(sfx.typ).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Sfx {
     /// The ID of the SFX.
@@ -41,6 +57,12 @@ impl Sfx {
     pub fn random_sound(&self, rng: &mut impl Rng) -> Option<&Sound> {
         self.sounds.choose(rng)
     }
+
+    /// Returns `true` if the SFX loops, i.e. its type is
+    /// [`SfxType::Five`] or [`SfxType::Six`].
+    pub fn is_looping(&self) -> bool {
+        matches!(self.typ, SfxType::Five | SfxType::Six)
+    }
 }
 
 /// The ID of a SFX.
@@ -54,7 +76,7 @@ impl Sfx {
 pub type SfxId = u8;
 
 #[repr(u8)]
-#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub enum SfxType {
     #[default]
@@ -108,7 +130,9 @@ bitflags! {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+crate::flags::impl_unknown_bits_set!(SfxFlags);
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[cfg_attr(feature = "bevy_reflect", derive(Reflect))]
 pub struct Sound {
     /// The file name of the sound excluding the path and extension, i.e. the
@@ -170,4 +194,49 @@ mod tests {
             "Playback rate out of range"
         );
     }
+
+    #[test]
+    fn test_packet_profile() {
+        let sound = Sound::default();
+        let packet = Packet {
+            name: "Foo".to_string(),
+            sfxs: HashMap::from([
+                (
+                    0,
+                    Sfx {
+                        id: 0,
+                        typ: SfxType::One,
+                        sounds: vec![sound.clone()],
+                        ..Default::default()
+                    },
+                ),
+                (
+                    1,
+                    Sfx {
+                        id: 1,
+                        typ: SfxType::Six,
+                        sounds: vec![sound.clone(), sound.clone(), sound.clone()],
+                        ..Default::default()
+                    },
+                ),
+                (
+                    2,
+                    Sfx {
+                        id: 2,
+                        typ: SfxType::Six,
+                        sounds: vec![sound],
+                        ..Default::default()
+                    },
+                ),
+            ]),
+        };
+
+        assert_eq!(packet.total_sound_count(), 5);
+        assert_eq!(
+            packet.sfx_count_by_type(),
+            HashMap::from([(SfxType::One, 1), (SfxType::Six, 2)])
+        );
+        assert!(!packet.sfxs[&0].is_looping());
+        assert!(packet.sfxs[&1].is_looping());
+    }
 }